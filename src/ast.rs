@@ -1,11 +1,60 @@
 // src/ast.rs
+use crate::span::{Span, Spanned};
+
 pub type ElseIf = (Expr, Vec<Stmt>);
 
+/// A type written in an annotation, e.g. `: number` or `: [string]`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeName {
+    Number,
+    Float,
+    Str,
+    Boolean,
+    Void,       // return types only
+    List(Box<TypeName>),
+}
+
+impl std::fmt::Display for TypeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TypeName::Number => write!(f, "number"),
+            TypeName::Float => write!(f, "float"),
+            TypeName::Str => write!(f, "string"),
+            TypeName::Boolean => write!(f, "boolean"),
+            TypeName::Void => write!(f, "void"),
+            TypeName::List(element) => write!(f, "[{}]", element),
+        }
+    }
+}
+
+pub type TypeAnn = Spanned<TypeName>;
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub ty: Option<TypeAnn>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub enum Expr {
+pub enum ExprKind {
     Int(i64),
     Float(f64),
     Str(String),
+    /// An interpolated string: its text and embedded expressions in order,
+    /// each converted to text and joined.
+    Interp(Vec<Expr>),
     Bool(bool),
     Ident(String),
     List(Vec<Expr>),
@@ -30,13 +79,29 @@ pub enum Expr {
 }
 
 #[derive(Debug, Clone)]
-pub enum Stmt {
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+    /// The `///` comment before a declaration, one line per line.
+    pub doc: Option<String>,
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Span) -> Self {
+        Self { kind, span, doc: None }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
     Const { 
         name: String, 
+        ty: Option<TypeAnn>,
         value: Expr 
     },
     Mutable { 
         name: String, 
+        ty: Option<TypeAnn>,
         init: Option<Expr> 
     },
     Assign { 
@@ -47,12 +112,14 @@ pub enum Stmt {
     Return(Option<Expr>),
     Course {           // Procedures (no return value)
         name: String, 
-        params: Vec<String>, 
+        params: Vec<Param>, 
+        ret: Option<TypeAnn>,
         body: Vec<Stmt> 
     },
     Purpose {          // Functions (can return values with yield)
         name: String, 
-        params: Vec<String>, 
+        params: Vec<Param>, 
+        ret: Option<TypeAnn>,
         body: Vec<Stmt> 
     },
     Persist { 
@@ -67,8 +134,9 @@ pub enum Stmt {
     },
     Iterate { 
         var: String, 
+        index: Option<String>,  // `iterate i, item across xs`
         iterable: Expr, 
         body: Vec<Stmt> 
     },
     Block(Vec<Stmt>),
-}
\ No newline at end of file
+}
@@ -0,0 +1,429 @@
+// src/bytecode.rs
+//
+// The `.flc` file format written by `flux compile` and loaded by
+// `flux run prog.flc`. All integers are little-endian; a string is a u32
+// byte length followed by UTF-8.
+//
+//   magic     b"FLXC"
+//   version   u16                  bumped whenever the layout or IR changes
+//   source    string               path of the `.fl` file, for diagnostics
+//   pool      u32 count, then a tag byte and payload per constant
+//   functions u32 count, then name (pool index), entry, arity, kind, locals
+//   globals   u32 count, then a name (pool index) per slot
+//   natives   u32 count, then a name (pool index) per native called
+//   code      u32 count, then an opcode byte and operands per instruction
+//   spans     start, end, line, col (u32 each) per instruction
+//   checksum  u64 FNV-1a of every byte before it
+use crate::codegen::{FuncKind, FuncTable, Function, IR};
+use crate::span::Span;
+use std::collections::HashMap;
+
+pub const MAGIC: &[u8; 4] = b"FLXC";
+pub const VERSION: u16 = 4;
+/// Local slots are addressed with a u16, so no function needs more.
+const MAX_LOCALS: usize = u16::MAX as usize + 1;
+
+/// A compiled program, ready for the VM.
+#[derive(Debug)]
+pub struct Program {
+    /// Path of the source file the spans point into.
+    pub source: String,
+    /// Global variable names, in slot order.
+    pub globals: Vec<String>,
+    /// Native functions called, linked by name when the program runs.
+    pub natives: Vec<String>,
+    pub(crate) code: Vec<IR>,
+    pub(crate) functions: FuncTable,
+    pub(crate) spans: Vec<Span>,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    NotBytecode,
+    Version(u16),
+    Truncated,
+    Checksum,
+    Corrupt(String),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LoadError::NotBytecode => write!(f, "not a Flux bytecode file"),
+            LoadError::Version(v) => write!(f, "bytecode version {} is not supported (expected {})", v, VERSION),
+            LoadError::Truncated => write!(f, "bytecode file is truncated"),
+            LoadError::Checksum => write!(f, "bytecode file is corrupt (checksum mismatch)"),
+            LoadError::Corrupt(what) => write!(f, "bytecode file is corrupt: {}", what),
+        }
+    }
+}
+
+/// A constant pool entry. Floats are kept as bits so entries can be hashed.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Constant {
+    Int(i64),
+    Float(u64),
+    Str(String),
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[derive(Default)]
+struct Writer {
+    out: Vec<u8>,
+    pool: Vec<Constant>,
+    index: HashMap<Constant, u32>,
+}
+
+impl Writer {
+    fn u8(&mut self, v: u8) { self.out.push(v); }
+    fn u16(&mut self, v: u16) { self.out.extend_from_slice(&v.to_le_bytes()); }
+    fn u32(&mut self, v: usize) { self.out.extend_from_slice(&(v as u32).to_le_bytes()); }
+    fn u64(&mut self, v: u64) { self.out.extend_from_slice(&v.to_le_bytes()); }
+
+    fn str(&mut self, s: &str) {
+        self.u32(s.len());
+        self.out.extend_from_slice(s.as_bytes());
+    }
+
+    /// Pool index of `c`, adding it on first use.
+    fn constant(&mut self, c: Constant) -> usize {
+        if let Some(&i) = self.index.get(&c) {
+            return i as usize;
+        }
+        let i = self.pool.len() as u32;
+        self.pool.push(c.clone());
+        self.index.insert(c, i);
+        i as usize
+    }
+}
+
+impl Program {
+    pub(crate) fn global_slot(&self, name: &str) -> Option<usize> {
+        self.globals.iter().position(|g| g == name)
+    }
+
+    /// The program as human-readable bytecode, as `--emit=ir` prints it.
+    pub fn disassemble(&self) -> String {
+        crate::emit::ir(self)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        // Instructions are encoded first so the pool is complete before
+        // it is written out ahead of them.
+        let mut body = Writer::default();
+        let mut functions: Vec<(&String, &Function)> = self.functions.iter().collect();
+        functions.sort_by_key(|(name, f)| (f.entry, name.as_str()));
+        body.u32(functions.len());
+        for (name, f) in functions {
+            let name = body.constant(Constant::Str(name.clone()));
+            body.u32(name);
+            body.u32(f.entry);
+            body.u32(f.arity);
+            body.u8(match f.kind { FuncKind::Course => 0, FuncKind::Purpose => 1 });
+            body.u32(f.locals);
+        }
+        body.u32(self.globals.len());
+        for name in &self.globals {
+            let name = body.constant(Constant::Str(name.clone()));
+            body.u32(name);
+        }
+        body.u32(self.natives.len());
+        for name in &self.natives {
+            let name = body.constant(Constant::Str(name.clone()));
+            body.u32(name);
+        }
+        body.u32(self.code.len());
+        for ir in &self.code {
+            encode_ir(&mut body, ir);
+        }
+        for span in &self.spans {
+            for v in [span.start, span.end, span.line, span.col] {
+                body.u32(v);
+            }
+        }
+
+        let mut w = Writer::default();
+        w.out.extend_from_slice(MAGIC);
+        w.u16(VERSION);
+        w.str(&self.source);
+        w.u32(body.pool.len());
+        for c in &body.pool {
+            match c {
+                Constant::Int(i) => { w.u8(0); w.u64(*i as u64); }
+                Constant::Float(bits) => { w.u8(1); w.u64(*bits); }
+                Constant::Str(s) => { w.u8(2); w.str(s); }
+            }
+        }
+        w.out.extend_from_slice(&body.out);
+        let checksum = fnv1a(&w.out);
+        w.u64(checksum);
+        w.out
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Program, LoadError> {
+        if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(LoadError::NotBytecode);
+        }
+        let mut r = Reader { bytes, pos: MAGIC.len(), pool: vec![] };
+        let version = r.u16()?;
+        if version != VERSION {
+            return Err(LoadError::Version(version));
+        }
+        let Some(body_len) = bytes.len().checked_sub(8) else { return Err(LoadError::Truncated) };
+        if body_len < r.pos {
+            return Err(LoadError::Truncated);
+        }
+        let stored = u64::from_le_bytes(bytes[body_len..].try_into().expect("8 bytes"));
+        if fnv1a(&bytes[..body_len]) != stored {
+            return Err(LoadError::Checksum);
+        }
+        r.bytes = &bytes[..body_len];
+
+        let source = r.str()?;
+        let mut pool = vec![];
+        for _ in 0..r.u32()? {
+            pool.push(match r.u8()? {
+                0 => Constant::Int(r.u64()? as i64),
+                1 => Constant::Float(r.u64()?),
+                2 => Constant::Str(r.str()?),
+                tag => return Err(LoadError::Corrupt(format!("unknown constant tag {}", tag))),
+            });
+        }
+        r.pool = pool;
+
+        let mut functions = HashMap::new();
+        for _ in 0..r.u32()? {
+            let name = r.string_constant()?;
+            let entry = r.u32()?;
+            let arity = r.u32()?;
+            let kind = match r.u8()? {
+                0 => FuncKind::Course,
+                1 => FuncKind::Purpose,
+                k => return Err(LoadError::Corrupt(format!("unknown function kind {}", k))),
+            };
+            let locals = r.u32()?;
+            functions.insert(name, Function { entry, arity, kind, locals });
+        }
+        let mut globals = vec![];
+        for _ in 0..r.u32()? {
+            globals.push(r.string_constant()?);
+        }
+        let mut natives = vec![];
+        for _ in 0..r.u32()? {
+            natives.push(r.string_constant()?);
+        }
+
+        let count = r.u32()?;
+        let mut code = Vec::with_capacity(count.min(r.remaining()));
+        for _ in 0..count {
+            code.push(r.ir()?);
+        }
+        let mut spans = Vec::with_capacity(count.min(r.remaining()));
+        for _ in 0..count {
+            spans.push(Span::new(r.u32()?, r.u32()?, r.u32()?, r.u32()?));
+        }
+        if r.remaining() != 0 {
+            return Err(LoadError::Corrupt("trailing bytes after the instructions".to_string()));
+        }
+
+        // Everything the VM indexes with must be in range.
+        for ir in &code {
+            if let IR::Jump(t) | IR::JumpFalse(t) | IR::JumpTrue(t) | IR::IterNext(t, _) = ir {
+                if *t > code.len() {
+                    return Err(LoadError::Corrupt(format!("jump target {} is out of range", t)));
+                }
+            }
+        }
+        let max_locals = functions.values().map(|f: &Function| f.locals).max().unwrap_or(0);
+        for ir in &code {
+            if let IR::LoadLocal(slot) | IR::StoreLocal(slot) = ir {
+                if *slot as usize >= max_locals {
+                    return Err(LoadError::Corrupt(format!("local slot {} is out of range", slot)));
+                }
+            }
+        }
+        for ir in &code {
+            if let IR::LoadGlobal(slot) | IR::StoreGlobal(slot) = ir {
+                if *slot as usize >= globals.len() {
+                    return Err(LoadError::Corrupt(format!("global slot {} is out of range", slot)));
+                }
+            }
+            if let IR::CallNative(index, _) = ir {
+                if *index as usize >= natives.len() {
+                    return Err(LoadError::Corrupt(format!("native {} is out of range", index)));
+                }
+            }
+        }
+        // Every value a count consumes needs an instruction of its own to push it.
+        for ir in &code {
+            let count = match ir {
+                IR::MakeList(n) | IR::Concat(n) | IR::CallNative(_, n) => *n,
+                IR::Call(name, argc) => {
+                    if functions.get(name).is_some_and(|f: &Function| f.arity != *argc) {
+                        return Err(LoadError::Corrupt(format!("call to `{}` with {} arguments", name, argc)));
+                    }
+                    *argc
+                }
+                _ => continue,
+            };
+            if count > code.len() {
+                return Err(LoadError::Corrupt(format!("operand count {} is out of range", count)));
+            }
+        }
+        for (name, f) in &functions {
+            if f.entry >= code.len() || f.locals < f.arity || f.locals > MAX_LOCALS {
+                return Err(LoadError::Corrupt(format!("invalid entry for function `{}`", name)));
+            }
+        }
+        if !matches!(code.last(), Some(IR::Return)) {
+            return Err(LoadError::Corrupt("code doesn't end with a return".to_string()));
+        }
+        Ok(Program { source, globals, natives, code, functions, spans })
+    }
+}
+
+fn encode_ir(w: &mut Writer, ir: &IR) {
+    match ir {
+        IR::PushI(i) => { w.u8(0); let c = w.constant(Constant::Int(*i)); w.u32(c); }
+        IR::PushF(f) => { w.u8(1); let c = w.constant(Constant::Float(f.to_bits())); w.u32(c); }
+        IR::PushS(s) => { w.u8(2); let c = w.constant(Constant::Str(s.clone())); w.u32(c); }
+        IR::PushB(b) => { w.u8(3); w.u8(*b as u8); }
+        IR::PushNull => w.u8(4),
+        IR::LoadGlobal(s) => { w.u8(5); w.u16(*s); }
+        IR::StoreGlobal(s) => { w.u8(6); w.u16(*s); }
+        IR::LoadLocal(s) => { w.u8(7); w.u16(*s); }
+        IR::StoreLocal(s) => { w.u8(8); w.u16(*s); }
+        IR::Pop => w.u8(9),
+        IR::Add => w.u8(10),
+        IR::Sub => w.u8(11),
+        IR::Mul => w.u8(12),
+        IR::Div => w.u8(13),
+        IR::Mod => w.u8(14),
+        IR::Power => w.u8(15),
+        IR::Neg => w.u8(16),
+        IR::Eq => w.u8(17),
+        IR::Neq => w.u8(18),
+        IR::Lt => w.u8(19),
+        IR::Gt => w.u8(20),
+        IR::Le => w.u8(21),
+        IR::Ge => w.u8(22),
+        IR::Not => w.u8(23),
+        IR::Jump(t) => { w.u8(24); w.u32(*t); }
+        IR::JumpFalse(t) => { w.u8(25); w.u32(*t); }
+        IR::JumpTrue(t) => { w.u8(26); w.u32(*t); }
+        IR::Call(name, argc) => {
+            w.u8(27);
+            let c = w.constant(Constant::Str(name.clone()));
+            w.u32(c);
+            w.u32(*argc);
+        }
+        IR::Return => w.u8(28),
+        IR::MakeList(n) => { w.u8(29); w.u32(*n); }
+        IR::GetIndex => w.u8(30),
+        IR::SetIndex => w.u8(31),
+        IR::IterInit => w.u8(32),
+        IR::IterRange => w.u8(33),
+        IR::IterNext(t, with_index) => { w.u8(34); w.u32(*t); w.u8(*with_index as u8); }
+        IR::CallNative(index, argc) => { w.u8(35); w.u16(*index); w.u32(*argc); }
+        IR::Concat(n) => { w.u8(36); w.u32(*n); }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    pool: Vec<Constant>,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], LoadError> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.bytes.len()).ok_or(LoadError::Truncated)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn remaining(&self) -> usize { self.bytes.len() - self.pos }
+    fn u8(&mut self) -> Result<u8, LoadError> { Ok(self.take(1)?[0]) }
+    fn u16(&mut self) -> Result<u16, LoadError> { Ok(u16::from_le_bytes(self.take(2)?.try_into().expect("2 bytes"))) }
+    fn u32(&mut self) -> Result<usize, LoadError> { Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("4 bytes")) as usize) }
+    fn u64(&mut self) -> Result<u64, LoadError> { Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes"))) }
+
+    fn str(&mut self) -> Result<String, LoadError> {
+        let len = self.u32()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| LoadError::Corrupt("string is not valid UTF-8".to_string()))
+    }
+
+    fn bool(&mut self) -> Result<bool, LoadError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(LoadError::Corrupt(format!("invalid boolean {}", b))),
+        }
+    }
+
+    fn constant(&mut self) -> Result<&Constant, LoadError> {
+        let i = self.u32()?;
+        self.pool.get(i).ok_or_else(|| LoadError::Corrupt(format!("constant {} is out of range", i)))
+    }
+
+    fn string_constant(&mut self) -> Result<String, LoadError> {
+        match self.constant()? {
+            Constant::Str(s) => Ok(s.clone()),
+            _ => Err(LoadError::Corrupt("expected a string constant".to_string())),
+        }
+    }
+
+    fn ir(&mut self) -> Result<IR, LoadError> {
+        Ok(match self.u8()? {
+            0 => match self.constant()? {
+                Constant::Int(i) => IR::PushI(*i),
+                _ => return Err(LoadError::Corrupt("expected an integer constant".to_string())),
+            },
+            1 => match self.constant()? {
+                Constant::Float(bits) => IR::PushF(f64::from_bits(*bits)),
+                _ => return Err(LoadError::Corrupt("expected a float constant".to_string())),
+            },
+            2 => IR::PushS(self.string_constant()?),
+            3 => IR::PushB(self.bool()?),
+            4 => IR::PushNull,
+            5 => IR::LoadGlobal(self.u16()?),
+            6 => IR::StoreGlobal(self.u16()?),
+            7 => IR::LoadLocal(self.u16()?),
+            8 => IR::StoreLocal(self.u16()?),
+            9 => IR::Pop,
+            10 => IR::Add,
+            11 => IR::Sub,
+            12 => IR::Mul,
+            13 => IR::Div,
+            14 => IR::Mod,
+            15 => IR::Power,
+            16 => IR::Neg,
+            17 => IR::Eq,
+            18 => IR::Neq,
+            19 => IR::Lt,
+            20 => IR::Gt,
+            21 => IR::Le,
+            22 => IR::Ge,
+            23 => IR::Not,
+            24 => IR::Jump(self.u32()?),
+            25 => IR::JumpFalse(self.u32()?),
+            26 => IR::JumpTrue(self.u32()?),
+            27 => IR::Call(self.string_constant()?, self.u32()?),
+            28 => IR::Return,
+            29 => IR::MakeList(self.u32()?),
+            30 => IR::GetIndex,
+            31 => IR::SetIndex,
+            32 => IR::IterInit,
+            33 => IR::IterRange,
+            34 => IR::IterNext(self.u32()?, self.bool()?),
+            35 => IR::CallNative(self.u16()?, self.u32()?),
+            36 => IR::Concat(self.u32()?),
+            op => return Err(LoadError::Corrupt(format!("unknown opcode {}", op))),
+        })
+    }
+}
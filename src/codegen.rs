@@ -1,44 +1,126 @@
 // src/codegen.rs
 use crate::ast::*;
+use crate::bytecode::Program;
+use crate::resolver::{Resolver, Slot};
+use crate::span::Span;
 use std::collections::HashMap;
 
 pub type IP = usize;
-pub type FuncTable = HashMap<String, IP>;
+pub type FuncTable = HashMap<String, Function>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuncKind {
+    Course,
+    Purpose,
+}
+
+impl std::fmt::Display for FuncKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FuncKind::Course => write!(f, "course"),
+            FuncKind::Purpose => write!(f, "purpose"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub entry: IP,
+    pub arity: usize,
+    pub kind: FuncKind,
+    /// Local slots a call needs, parameters included.
+    pub locals: usize,
+}
 
 #[derive(Debug, Clone)]
 pub enum IR {
     PushI(i64), PushF(f64), PushS(String), PushB(bool), PushNull,
-    Load(String), Store(String),
-    Add, Sub, Mul, Div, Mod, Power,
-    Eq, Neq, Lt, Gt, Le, Ge, And, Or, Not,
-    Jump(IP), JumpFalse(IP),
+    LoadGlobal(u16), StoreGlobal(u16),
+    LoadLocal(u16), StoreLocal(u16),
+    Pop,
+    Add, Sub, Mul, Div, Mod, Power, Neg,
+    Eq, Neq, Lt, Gt, Le, Ge, Not,
+    Jump(IP), JumpFalse(IP), JumpTrue(IP),
     Call(String, usize), Return,
+    /// Call the native at this index of the program's native table.
+    CallNative(u16, usize),
     // List operations
-    MakeList(usize), GetIndex, SetIndex, ListLen,
+    MakeList(usize), GetIndex, SetIndex,
+    /// Pop this many values and push them shown as text, joined.
+    Concat(usize),
+    // Iteration: `IterNext` pushes the next item (after its index when the
+    // flag is set), or drops the iterator and jumps when it is exhausted.
+    IterInit, IterRange, IterNext(IP, bool),
 }
 
 pub struct Codegen {
     pub code: Vec<IR>,
     pub functions: FuncTable,
+    /// Source span for each instruction in `code`, index for index.
+    pub spans: Vec<Span>,
+    /// Names of the native functions called, indexed by `CallNative`.
+    pub natives: Vec<String>,
+    span: Span,
+    resolver: Resolver,
+    /// Function being compiled; `None` at top level.
+    function: Option<String>,
 }
 
 impl Codegen {
     pub fn new() -> Self { 
         Self { 
             code: Vec::with_capacity(8192), 
-            functions: HashMap::new() 
+            functions: HashMap::new(),
+            spans: Vec::with_capacity(8192),
+            natives: Vec::new(),
+            span: Span::default(),
+            resolver: Resolver::new(),
+            function: None,
         } 
     }
 
+    /// Everything compiled so far, as a program whose spans point into the
+    /// file at `path`.
+    pub fn program(&self, path: &str) -> Program {
+        Program {
+            source: path.to_string(),
+            globals: self.resolver.globals().to_vec(),
+            natives: self.natives.clone(),
+            code: self.code.clone(),
+            functions: self.functions.clone(),
+            spans: self.spans.clone(),
+        }
+    }
+
     fn emit(&mut self, op: IR) -> usize { 
         let p = self.code.len(); 
         self.code.push(op); 
+        self.spans.push(self.span);
         p 
     }
     
+    fn slot(&self, name: &str) -> Slot {
+        self.resolver.lookup(self.function.as_deref(), name)
+            .expect("resolver assigns a slot to every name")
+    }
+
+    fn load(&mut self, name: &str) {
+        match self.slot(name) {
+            Slot::Local(i) => self.emit(IR::LoadLocal(i)),
+            Slot::Global(i) => self.emit(IR::LoadGlobal(i)),
+        };
+    }
+
+    fn store(&mut self, name: &str) {
+        match self.slot(name) {
+            Slot::Local(i) => self.emit(IR::StoreLocal(i)),
+            Slot::Global(i) => self.emit(IR::StoreGlobal(i)),
+        };
+    }
+
     fn patch(&mut self, pos: usize, target: IP) {
         match &mut self.code[pos] {
-            IR::Jump(t) | IR::JumpFalse(t) => *t = target,
+            IR::Jump(t) | IR::JumpFalse(t) | IR::JumpTrue(t) | IR::IterNext(t, _) => *t = target,
             _ => {}
         }
     }
@@ -47,18 +129,21 @@ impl Codegen {
 	pub fn compile(&mut self, stmts: &[Stmt]) {
 		// 1. New: Reserve a spot for the initial jump to the main execution code.
 		// The target is temporarily set to 0.
+		self.resolver.resolve(stmts);
+		// Compiling onto earlier code must not reuse its last span.
+		self.span = Span::default();
 		let main_jump_pos = self.emit(IR::Jump(0));
 
 		// STEP 1: Compile ALL function definitions FIRST (Code will be placed before the jump target)
 		for s in stmts {
-			if let Stmt::Course { name, params, body } | Stmt::Purpose { name, params, body } = s {
+			if let StmtKind::Course { name, params, body, .. } | StmtKind::Purpose { name, params, body, .. } = &s.kind {
+				self.span = s.span;
+				let kind = if matches!(s.kind, StmtKind::Course { .. }) { FuncKind::Course } else { FuncKind::Purpose };
 				let entry = self.code.len();
-				self.functions.insert(name.clone(), entry);
-				
-				// Function prologue: store parameters
-				for p in params.iter().rev() { 
-					self.emit(IR::Store(p.clone())); 
-				}
+				let locals = self.resolver.scope(name).map_or(params.len(), |s| s.slot_count());
+				self.functions.insert(name.clone(), Function { entry, arity: params.len(), kind, locals });
+				// Arguments land in local slots 0..arity when the call is made.
+				self.function = Some(name.clone());
 				
 				// Function body
 				for stmt in body { 
@@ -68,6 +153,7 @@ impl Codegen {
 				// Function epilogue: ensure return
 				self.emit(IR::PushNull);
 				self.emit(IR::Return);
+				self.function = None;
 			}
 		}
 		
@@ -76,19 +162,20 @@ impl Codegen {
 		
 		// 3. New: Patch the initial jump to point to the start of the global statements.
 		self.patch(main_jump_pos, main_entry_ip);
+		// The last function's span must not carry over to main's epilogue.
+		self.span = Span::default();
 
 		// STEP 2: Compile global statements ONLY
 		for s in stmts {
-			match s {
-				Stmt::Const { .. } | Stmt::Mutable { .. } | Stmt::Assign { .. } | 
-				Stmt::Expr(_) | Stmt::Iterate { .. } | Stmt::Persist { .. } | 
-				Stmt::When { .. } | Stmt::Block(_) => {
+			match &s.kind {
+				StmtKind::Const { .. } | StmtKind::Mutable { .. } | StmtKind::Assign { .. } | 
+				StmtKind::Expr(_) | StmtKind::Iterate { .. } | StmtKind::Persist { .. } | 
+				StmtKind::When { .. } | StmtKind::Block(_) | StmtKind::Return(_) => {
 					self.stmt(s);
 				}
-				Stmt::Course { .. } | Stmt::Purpose { .. } => {
+				StmtKind::Course { .. } | StmtKind::Purpose { .. } => {
 					// Already compiled in step 1, and now execution will jump over them.
 				}
-				_ => {}
 			}
 		}
 		
@@ -98,42 +185,49 @@ impl Codegen {
 	}
 
     fn stmt(&mut self, s: &Stmt) {
-        match s {
-            Stmt::Const { name, value } | Stmt::Mutable { name, init: Some(value), .. } => {
+        let outer = std::mem::replace(&mut self.span, s.span);
+        self.stmt_kind(s);
+        self.span = outer;
+    }
+
+    fn stmt_kind(&mut self, s: &Stmt) {
+        match &s.kind {
+            StmtKind::Const { name, value, .. } | StmtKind::Mutable { name, init: Some(value), .. } => {
                 self.expr(value);
-                self.emit(IR::Store(name.clone()));
+                self.store(name);
             }
-            Stmt::Mutable { name, init: None, .. } => {
+            StmtKind::Mutable { name, init: None, .. } => {
                 self.emit(IR::PushNull);
-                self.emit(IR::Store(name.clone()));
+                self.store(name);
             }
-            Stmt::Assign { name, value } => {
+            StmtKind::Assign { name, value } => {
                 // Handle list assignment: name[index] = value
-                if let Expr::Index { target, index, value: assignment_value } = value {
-                    if let Expr::Ident(var_name) = &**target {
+                if let ExprKind::Index { target, index, value: assignment_value } = &value.kind {
+                    if let ExprKind::Ident(var_name) = &target.kind {
                         // Load the list, index, and value
-                        self.emit(IR::Load(var_name.clone()));
-                        self.expr(&index);
+                        self.load(var_name);
+                        self.expr(index);
                         if let Some(assignment_value) = assignment_value {
-                            self.expr(&assignment_value);
+                            self.expr(assignment_value);
                         } else {
                             self.emit(IR::PushNull);
                         }
                         self.emit(IR::SetIndex);
-                        self.emit(IR::Store(var_name.clone())); // Store back the modified list
+                        self.store(var_name); // Store back the modified list
                         return;
                     }
                 }
                 // Regular assignment
                 self.expr(value);
-                self.emit(IR::Store(name.clone()));
+                self.store(name);
             }
-            Stmt::Expr(e) => { 
+            StmtKind::Expr(e) => { 
                 self.expr(e);
+                self.emit(IR::Pop);
             }
-            Stmt::Return(Some(e)) => { self.expr(e); self.emit(IR::Return); }
-            Stmt::Return(None) => { self.emit(IR::PushNull); self.emit(IR::Return); }
-            Stmt::Persist { cond, body } => {
+            StmtKind::Return(Some(e)) => { self.expr(e); self.emit(IR::Return); }
+            StmtKind::Return(None) => { self.emit(IR::PushNull); self.emit(IR::Return); }
+            StmtKind::Persist { cond, body } => {
                 let start = self.code.len();
                 self.expr(cond);
                 let jf = self.emit(IR::JumpFalse(0));
@@ -141,91 +235,56 @@ impl Codegen {
                 self.emit(IR::Jump(start));
                 self.patch(jf, self.code.len());
             }
-            Stmt::When { cond, then, elifs, otherwise } => {
+            StmtKind::When { cond, then, elifs, otherwise } => {
                 self.expr(cond);
                 let mut exit_jumps = vec![];
-                let mut cond_jumps = vec![self.emit(IR::JumpFalse(0))];
+                let mut next_branch = self.emit(IR::JumpFalse(0));
 
                 for s in then { self.stmt(s); }
                 exit_jumps.push(self.emit(IR::Jump(0)));
-                self.patch(cond_jumps[0], self.code.len());
+                self.patch(next_branch, self.code.len());
 
                 for (c, b) in elifs {
                     self.expr(c);
-                    let j = self.emit(IR::JumpFalse(0));
-                    cond_jumps.push(j);
+                    next_branch = self.emit(IR::JumpFalse(0));
                     for s in b { self.stmt(s); }
                     exit_jumps.push(self.emit(IR::Jump(0)));
+                    self.patch(next_branch, self.code.len());
                 }
                 for s in otherwise { self.stmt(s); }
 
                 let end = self.code.len();
                 for j in exit_jumps { self.patch(j, end); }
             }
-            Stmt::Iterate { var, iterable, body } => {
-                // Check if this is a range iteration (1 to 10)
-                if let Expr::Binary { left, op: crate::lexer::Token::To, right } = iterable {
-                    // Range iteration: variable i = start
+            StmtKind::Iterate { var, index, iterable, body } => {
+                // Loop state lives on the VM's iterator stack, so nested loops
+                // and loops in called functions each get their own.
+                if let ExprKind::Binary { left, op: crate::lexer::Token::To, right } = &iterable.kind {
+                    // Range iteration (1 to 10): bounds are evaluated once
                     self.expr(left);
-                    self.emit(IR::Store(var.clone()));
-                    
-                    let loop_start = self.code.len();
-                    
-                    // Condition: i <= end
-                    self.emit(IR::Load(var.clone()));
                     self.expr(right);
-                    self.emit(IR::Le);
-                    let jf = self.emit(IR::JumpFalse(0));
-                    
-                    // Loop body
-                    for b in body { self.stmt(b); }
-                    
-                    // Increment: i = i + 1
-                    self.emit(IR::Load(var.clone()));
-                    self.emit(IR::PushI(1));
-                    self.emit(IR::Add);
-                    self.emit(IR::Store(var.clone()));
-                    
-                    // Jump back
-                    self.emit(IR::Jump(loop_start));
-                    self.patch(jf, self.code.len());
+                    self.emit(IR::IterRange);
                 } else {
-                    // Iterate over list or other iterable
                     self.expr(iterable);
-                    self.emit(IR::Store("_iter_list".to_string()));
-                    self.emit(IR::PushI(0));
-                    self.emit(IR::Store("_iter_index".to_string()));
-                    
-                    let loop_start = self.code.len();
-                    self.emit(IR::Load("_iter_index".to_string()));
-                    self.emit(IR::Load("_iter_list".to_string()));
-                    self.emit(IR::ListLen);
-                    self.emit(IR::Lt);
-                    let jf = self.emit(IR::JumpFalse(0));
-                    
-                    // Get current element
-                    self.emit(IR::Load("_iter_list".to_string()));
-                    self.emit(IR::Load("_iter_index".to_string()));
-                    self.emit(IR::GetIndex);
-                    self.emit(IR::Store(var.clone()));
-                    
-                    // Loop body
-                    for b in body { self.stmt(b); }
-                    
-                    // Increment index
-                    self.emit(IR::Load("_iter_index".to_string()));
-                    self.emit(IR::PushI(1));
-                    self.emit(IR::Add);
-                    self.emit(IR::Store("_iter_index".to_string()));
-                    
-                    self.emit(IR::Jump(loop_start));
-                    self.patch(jf, self.code.len());
+                    self.emit(IR::IterInit);
                 }
+                
+                let loop_start = self.emit(IR::IterNext(0, index.is_some()));
+                self.store(var);
+                if let Some(index) = index {
+                    self.store(index);
+                }
+                
+                // Loop body
+                for b in body { self.stmt(b); }
+                
+                self.emit(IR::Jump(loop_start));
+                self.patch(loop_start, self.code.len());
             }
-            Stmt::Course { .. } | Stmt::Purpose { .. } => {
+            StmtKind::Course { .. } | StmtKind::Purpose { .. } => {
                 // These are handled separately in compile()
             }
-            Stmt::Block(body) => {
+            StmtKind::Block(body) => {
                 for stmt in body {
                     self.stmt(stmt);
                 }
@@ -234,33 +293,74 @@ impl Codegen {
     }
 
     fn expr(&mut self, e: &Expr) {
-        match e {
-            Expr::Int(i) => { self.emit(IR::PushI(*i)); }
-            Expr::Float(f) => { self.emit(IR::PushF(*f)); }
-            Expr::Str(s) => { self.emit(IR::PushS(s.clone())); }
-            Expr::Bool(b) => { self.emit(IR::PushB(*b)); }
-            Expr::List(elements) => {
+        let outer = std::mem::replace(&mut self.span, e.span);
+        self.expr_kind(e);
+        self.span = outer;
+    }
+
+    fn expr_kind(&mut self, e: &Expr) {
+        match &e.kind {
+            ExprKind::Int(i) => { self.emit(IR::PushI(*i)); }
+            ExprKind::Float(f) => { self.emit(IR::PushF(*f)); }
+            ExprKind::Str(s) => { self.emit(IR::PushS(s.clone())); }
+            ExprKind::Bool(b) => { self.emit(IR::PushB(*b)); }
+            ExprKind::Interp(parts) => {
+                for part in parts {
+                    self.expr(part);
+                }
+                self.emit(IR::Concat(parts.len()));
+            }
+            ExprKind::List(elements) => {
                 for elem in elements {
                     self.expr(elem);
                 }
                 self.emit(IR::MakeList(elements.len()));
             }
-            Expr::Ident(n) => { self.emit(IR::Load(n.clone())); }
-            Expr::Call { callee, args } => {
+            ExprKind::Ident(n) => { self.load(n); }
+            ExprKind::Call { callee, args } => {
                 for a in args { self.expr(a); }
-                self.emit(IR::Call(callee.clone(), args.len()));
+                // Anything that isn't a course or purpose of the program is
+                // a native; the VM links it by name before running.
+                if self.resolver.scope(callee).is_some() {
+                    self.emit(IR::Call(callee.clone(), args.len()));
+                } else {
+                    let index = match self.natives.iter().position(|n| n == callee) {
+                        Some(i) => i,
+                        None => {
+                            self.natives.push(callee.clone());
+                            self.natives.len() - 1
+                        }
+                    };
+                    self.emit(IR::CallNative(index as u16, args.len()));
+                }
             }
-            Expr::Index { target, index, value } => {
+            ExprKind::Index { target, index, value } => {
                 self.expr(target);
                 self.expr(index);
                 if let Some(assignment_value) = value {
-                    self.expr(&assignment_value);
+                    self.expr(assignment_value);
                     self.emit(IR::SetIndex);
                 } else {
                     self.emit(IR::GetIndex);
                 }
             }
-            Expr::Binary { left, op, right } => {
+            ExprKind::Binary { left, op: op @ (crate::lexer::Token::And | crate::lexer::Token::Or), right } => {
+                // Short-circuit: the right operand only runs when the left one
+                // doesn't decide the result. Both forms yield a boolean.
+                let is_and = matches!(op, crate::lexer::Token::And);
+                let decide = |target| if is_and { IR::JumpFalse(target) } else { IR::JumpTrue(target) };
+                self.expr(left);
+                let left_decides = self.emit(decide(0));
+                self.expr(right);
+                let right_decides = self.emit(decide(0));
+                self.emit(IR::PushB(is_and));
+                let done = self.emit(IR::Jump(0));
+                self.patch(left_decides, self.code.len());
+                self.patch(right_decides, self.code.len());
+                self.emit(IR::PushB(!is_and));
+                self.patch(done, self.code.len());
+            }
+            ExprKind::Binary { left, op, right } => {
                 self.expr(left);
                 self.expr(right);
                 match op {
@@ -276,8 +376,6 @@ impl Codegen {
                     crate::lexer::Token::Gt => { self.emit(IR::Gt); }
                     crate::lexer::Token::LtEq => { self.emit(IR::Le); }
                     crate::lexer::Token::GtEq => { self.emit(IR::Ge); }
-                    crate::lexer::Token::And => { self.emit(IR::And); }
-                    crate::lexer::Token::Or => { self.emit(IR::Or); }
                     crate::lexer::Token::To => { 
                         // 'to' operator used in ranges - handled in iterate loops
                         self.emit(IR::Le);
@@ -285,12 +383,11 @@ impl Codegen {
                     _ => {}
                 }
             }
-            Expr::Unary { op: crate::lexer::Token::Minus, expr } => {
+            ExprKind::Unary { op: crate::lexer::Token::Minus, expr } => {
                 self.expr(expr);
-                self.emit(IR::PushI(-1));
-                self.emit(IR::Mul);
+                self.emit(IR::Neg);
             }
-            Expr::Unary { op: crate::lexer::Token::Not, expr } => {
+            ExprKind::Unary { op: crate::lexer::Token::Not, expr } => {
                 self.expr(expr);
                 self.emit(IR::Not);
             }
@@ -0,0 +1,198 @@
+// src/diagnostic.rs
+use crate::error::FluxError;
+use crate::span::Span;
+use std::io::IsTerminal;
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+/// A user-facing error report: headline, optional code, labelled source
+/// spans, and trailing `note:` / `help:` lines.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            code: None,
+            message: message.into(),
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+}
+
+impl From<&FluxError> for Diagnostic {
+    fn from(err: &FluxError) -> Self {
+        let mut diag = Diagnostic::error(err.message()).with_code(err.code());
+        if let Some(span) = err.span() {
+            diag = diag.with_label(span, "");
+        }
+        for (span, text) in err.labels() {
+            diag = diag.with_secondary(*span, text.clone());
+        }
+        // Deep recursion would otherwise bury the error under its own trace.
+        const SHOWN_FRAMES: usize = 10;
+        let trace = err.trace();
+        for frame in trace.iter().take(SHOWN_FRAMES) {
+            diag = diag.with_note(frame.to_string());
+        }
+        if trace.len() > SHOWN_FRAMES {
+            diag = diag.with_note(format!("... and {} more calls", trace.len() - SHOWN_FRAMES));
+        }
+        if let Some(help) = err.help() {
+            diag = diag.with_help(help);
+        }
+        diag
+    }
+}
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// Resolve `Auto` against stderr, honouring the `NO_COLOR` convention.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+/// Renders diagnostics against one source file, rustc style.
+pub struct Renderer<'a> {
+    path: &'a str,
+    source: &'a str,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(path: &'a str, source: &'a str, color: bool) -> Self {
+        Self { path, source, color }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color && !text.is_empty() {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn render(&self, diag: &Diagnostic) -> String {
+        let mut out = String::new();
+        let head = match diag.code {
+            Some(code) => format!("error[{}]", code),
+            None => "error".to_string(),
+        };
+        out.push_str(&self.paint(RED, &head));
+        out.push_str(&self.paint(BOLD, &format!(": {}", diag.message)));
+        out.push('\n');
+
+        let mut labels = diag.labels.clone();
+        labels.sort_by_key(|l| (l.span.line, l.span.col));
+        let width = labels.iter().map(|l| l.span.line.to_string().len()).max().unwrap_or(0);
+        let gutter = " ".repeat(width);
+
+        if let Some(first) = diag.labels.iter().find(|l| l.primary).or(diag.labels.first()) {
+            out.push_str(&format!("{}{} {}:{}:{}\n", gutter, self.paint(BLUE, "-->"), self.path, first.span.line, first.span.col));
+            out.push_str(&format!("{} {}\n", gutter, self.paint(BLUE, "|")));
+        }
+
+        // Without the source (a `.flc` whose `.fl` is gone) only the location is shown.
+        if self.source.is_empty() {
+            labels.clear();
+        }
+        let mut last_line = None;
+        for label in &labels {
+            let line_no = label.span.line;
+            let text = self.source.lines().nth(line_no.saturating_sub(1)).unwrap_or("");
+            if last_line != Some(line_no) {
+                if matches!(last_line, Some(prev) if line_no > prev + 1) {
+                    out.push_str(&format!("{}\n", self.paint(BLUE, "...")));
+                }
+                out.push_str(&format!("{} {} {}\n", self.paint(BLUE, &format!("{:>width$}", line_no)), self.paint(BLUE, "|"), text));
+            }
+            last_line = Some(line_no);
+
+            // Keep tabs in the padding so the marker lines up under the source.
+            let padding: String = text.chars()
+                .take(label.span.col.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let line_rest = text.chars().count().saturating_sub(label.span.col.saturating_sub(1));
+            let covered = self.source.get(label.span.start..label.span.end)
+                .map(|s| s.lines().next().unwrap_or("").chars().count())
+                .unwrap_or(0);
+            let len = covered.min(line_rest).max(1);
+            let (mark, style) = if label.primary { ("^", RED) } else { ("-", BLUE) };
+            let underline = mark.repeat(len);
+            let message = if label.message.is_empty() { String::new() } else { format!(" {}", label.message) };
+            out.push_str(&format!("{} {} {}{}\n", gutter, self.paint(BLUE, "|"), padding, self.paint(style, &format!("{}{}", underline, message))));
+        }
+
+        for note in &diag.notes {
+            out.push_str(&format!("{} {} {}: {}\n", gutter, self.paint(BLUE, "="), self.paint(BOLD, "note"), note));
+        }
+        for help in &diag.help {
+            out.push_str(&format!("{} {} {}: {}\n", gutter, self.paint(BLUE, "="), self.paint(BOLD, "help"), help));
+        }
+        out
+    }
+}
@@ -0,0 +1,283 @@
+// src/doc.rs
+//
+// `flux doc`: a reference for one program, built from its top-level
+// declarations and their `///` comments. Each course, purpose and constant,
+// and each documented mutable, gets its signature as written and its doc
+// text; functions also link to the functions they call and the ones that
+// call them, and a name in backticks in doc text links to that declaration.
+use crate::ast::*;
+use crate::emit::annotated;
+use std::collections::HashSet;
+
+/// What `flux doc` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+impl DocFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "markdown" | "md" => Some(DocFormat::Markdown),
+            "html" => Some(DocFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// A top-level declaration and what the reference says about it.
+struct Item<'a> {
+    kind: &'static str,
+    name: &'a str,
+    signature: String,
+    doc: &'a str,
+    line: usize,
+    calls: Vec<&'a str>,
+    called_by: Vec<&'a str>,
+}
+
+impl Item<'_> {
+    /// The id of the item's heading: what Markdown renderers derive from
+    /// `kind name`, so links work both there and in the HTML page.
+    fn anchor(&self) -> String {
+        format!("{}-{}", self.kind, self.name).to_lowercase()
+    }
+
+    fn is_function(&self) -> bool {
+        matches!(self.kind, "course" | "purpose")
+    }
+
+    /// The first line of the doc text.
+    fn summary(&self) -> &str {
+        self.doc.lines().next().unwrap_or("")
+    }
+}
+
+const SECTIONS: [(&str, &str); 4] =
+    [("purpose", "Purposes"), ("course", "Courses"), ("constant", "Constants"), ("mutable", "Variables")];
+
+fn items(program: &[Stmt]) -> Vec<Item<'_>> {
+    let mut items = vec![];
+    for s in program {
+        let (kind, name, signature, calls) = match &s.kind {
+            StmtKind::Course { name, params, ret, body } | StmtKind::Purpose { name, params, ret, body } => {
+                let kind = if matches!(s.kind, StmtKind::Course { .. }) { "course" } else { "purpose" };
+                let params: Vec<String> = params.iter().map(|p| annotated(&p.name, &p.ty)).collect();
+                let ret = ret.as_ref().map(|r| format!(": {}", r.node)).unwrap_or_default();
+                let mut calls = vec![];
+                stmts_calls(body, &mut calls);
+                (kind, name, format!("{} {}({}){}", kind, name, params.join(", "), ret), calls)
+            }
+            StmtKind::Const { name, ty, .. } => ("constant", name, format!("constant {}", annotated(name, ty)), vec![]),
+            // Most top-level mutables are working state; only the ones the
+            // author documented belong in the reference.
+            StmtKind::Mutable { name, ty, .. } if s.doc.is_some() => ("mutable", name, format!("mutable {}", annotated(name, ty)), vec![]),
+            _ => continue,
+        };
+        let doc = s.doc.as_deref().unwrap_or("");
+        items.push(Item { kind, name, signature, doc, line: s.span.line, calls, called_by: vec![] });
+    }
+
+    // Natives such as `report` have nothing to link to.
+    let functions: HashSet<&str> = items.iter().filter(|i| i.is_function()).map(|i| i.name).collect();
+    for item in &mut items {
+        item.calls.retain(|c| functions.contains(c));
+        item.calls.sort_unstable();
+        item.calls.dedup();
+    }
+    for i in 0..items.len() {
+        let name = items[i].name;
+        items[i].called_by = items.iter().filter(|other| other.calls.contains(&name)).map(|other| other.name).collect();
+    }
+    items
+}
+
+fn stmts_calls<'a>(stmts: &'a [Stmt], calls: &mut Vec<&'a str>) {
+    for s in stmts {
+        match &s.kind {
+            StmtKind::Const { value, .. } | StmtKind::Mutable { init: Some(value), .. } |
+            StmtKind::Assign { value, .. } | StmtKind::Expr(value) | StmtKind::Return(Some(value)) => expr_calls(value, calls),
+            StmtKind::Mutable { init: None, .. } | StmtKind::Return(None) => {}
+            StmtKind::Course { body, .. } | StmtKind::Purpose { body, .. } | StmtKind::Block(body) => stmts_calls(body, calls),
+            StmtKind::Persist { cond, body } => {
+                expr_calls(cond, calls);
+                stmts_calls(body, calls);
+            }
+            StmtKind::When { cond, then, elifs, otherwise } => {
+                expr_calls(cond, calls);
+                stmts_calls(then, calls);
+                for (c, b) in elifs {
+                    expr_calls(c, calls);
+                    stmts_calls(b, calls);
+                }
+                stmts_calls(otherwise, calls);
+            }
+            StmtKind::Iterate { iterable, body, .. } => {
+                expr_calls(iterable, calls);
+                stmts_calls(body, calls);
+            }
+        }
+    }
+}
+
+fn expr_calls<'a>(e: &'a Expr, calls: &mut Vec<&'a str>) {
+    match &e.kind {
+        ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Str(_) | ExprKind::Bool(_) | ExprKind::Ident(_) => {}
+        ExprKind::List(elements) | ExprKind::Interp(elements) => {
+            for el in elements { expr_calls(el, calls); }
+        }
+        ExprKind::Binary { left, right, .. } => {
+            expr_calls(left, calls);
+            expr_calls(right, calls);
+        }
+        ExprKind::Unary { expr, .. } => expr_calls(expr, calls),
+        ExprKind::Call { callee, args } => {
+            calls.push(callee);
+            for a in args { expr_calls(a, calls); }
+        }
+        ExprKind::Index { target, index, value } => {
+            expr_calls(target, calls);
+            expr_calls(index, calls);
+            if let Some(v) = value { expr_calls(v, calls); }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A link to `item`, shown as its name in code.
+fn link(item: &Item, format: DocFormat) -> String {
+    match format {
+        DocFormat::Markdown => format!("[`{}`](#{})", item.name, item.anchor()),
+        DocFormat::Html => format!("<a href=\"#{}\"><code>{}</code></a>", item.anchor(), escape(item.name)),
+    }
+}
+
+fn links(names: &[&str], items: &[Item], format: DocFormat) -> String {
+    let links: Vec<String> = names.iter()
+        .filter_map(|name| items.iter().find(|i| i.name == *name && i.is_function()))
+        .map(|item| link(item, format))
+        .collect();
+    links.join(", ")
+}
+
+/// Doc text with each `` `name` `` of another declaration turned into a
+/// link. In HTML the rest of the text is escaped and code spans marked up.
+fn inline(text: &str, items: &[Item], current: &str, format: DocFormat) -> String {
+    let pieces: Vec<&str> = text.split('`').collect();
+    let mut out = String::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        // Odd pieces sit between backticks, unless the last one is unclosed.
+        if i % 2 == 1 && i + 1 < pieces.len() {
+            let target = items.iter().find(|item| item.name == *piece && item.name != current);
+            out.push_str(&match (target, format) {
+                (Some(item), _) => link(item, format),
+                (None, DocFormat::Markdown) => format!("`{}`", piece),
+                (None, DocFormat::Html) => format!("<code>{}</code>", escape(piece)),
+            });
+        } else {
+            let piece = if i % 2 == 1 { format!("`{}", piece) } else { piece.to_string() };
+            out.push_str(&match format {
+                DocFormat::Markdown => piece,
+                DocFormat::Html => escape(&piece),
+            });
+        }
+    }
+    out
+}
+
+/// The reference for `program`, titled `title`.
+pub fn render(title: &str, program: &[Stmt], format: DocFormat) -> String {
+    let items = items(program);
+    match format {
+        DocFormat::Markdown => markdown(title, &items),
+        DocFormat::Html => html(title, &items),
+    }
+}
+
+fn markdown(title: &str, items: &[Item]) -> String {
+    let mut out = format!("# {}\n", title);
+    if items.is_empty() {
+        out.push_str("\nNothing to document: no courses, purposes, constants or documented variables.\n");
+        return out;
+    }
+    out.push('\n');
+    for item in items {
+        let summary = inline(item.summary(), items, item.name, DocFormat::Markdown);
+        out.push_str(&format!("- {} {}{}\n", item.kind, link(item, DocFormat::Markdown),
+            if summary.is_empty() { String::new() } else { format!(": {}", summary) }));
+    }
+    for (kind, heading) in SECTIONS {
+        let section: Vec<&Item> = items.iter().filter(|i| i.kind == kind).collect();
+        if section.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {}\n", heading));
+        for item in section {
+            out.push_str(&format!("\n### {} `{}`\n\n```flux\n{}\n```\n", item.kind, item.name, item.signature));
+            if !item.doc.is_empty() {
+                out.push_str(&format!("\n{}\n", inline(item.doc, items, item.name, DocFormat::Markdown)));
+            }
+            if !item.calls.is_empty() {
+                out.push_str(&format!("\nCalls: {}\n", links(&item.calls, items, DocFormat::Markdown)));
+            }
+            if !item.called_by.is_empty() {
+                out.push_str(&format!("\nCalled by: {}\n", links(&item.called_by, items, DocFormat::Markdown)));
+            }
+            out.push_str(&format!("\n*Defined on line {}.*\n", item.line));
+        }
+    }
+    out
+}
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5; }
+code, pre { font-family: ui-monospace, monospace; background: #f3f3f3; border-radius: 3px; }
+code { padding: 0 0.2em; }
+pre { padding: 0.6rem 0.8rem; overflow-x: auto; }
+pre code { padding: 0; }
+section { border-top: 1px solid #ddd; margin-top: 1.5rem; }
+.meta { color: #666; font-size: 0.9em; }";
+
+fn html(title: &str, items: &[Item]) -> String {
+    let mut out = format!("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+        <style>\n{1}\n</style>\n</head>\n<body>\n<h1>{0}</h1>\n", escape(title), STYLE);
+    if items.is_empty() {
+        out.push_str("<p>Nothing to document: no courses, purposes, constants or documented variables.</p>\n");
+    } else {
+        out.push_str("<ul>\n");
+        for item in items {
+            let summary = inline(item.summary(), items, item.name, DocFormat::Html);
+            out.push_str(&format!("<li>{} {}{}</li>\n", item.kind, link(item, DocFormat::Html),
+                if summary.is_empty() { String::new() } else { format!(": {}", summary) }));
+        }
+        out.push_str("</ul>\n");
+    }
+    for (kind, heading) in SECTIONS {
+        let section: Vec<&Item> = items.iter().filter(|i| i.kind == kind).collect();
+        if section.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("<h2>{}</h2>\n", heading));
+        for item in section {
+            out.push_str(&format!("<section id=\"{}\">\n<h3>{} <code>{}</code></h3>\n<pre><code>{}</code></pre>\n",
+                item.anchor(), item.kind, escape(item.name), escape(&item.signature)));
+            // A blank `///` line separates paragraphs.
+            for paragraph in item.doc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                out.push_str(&format!("<p>{}</p>\n", inline(paragraph, items, item.name, DocFormat::Html)));
+            }
+            if !item.calls.is_empty() {
+                out.push_str(&format!("<p class=\"meta\">Calls: {}</p>\n", links(&item.calls, items, DocFormat::Html)));
+            }
+            if !item.called_by.is_empty() {
+                out.push_str(&format!("<p class=\"meta\">Called by: {}</p>\n", links(&item.called_by, items, DocFormat::Html)));
+            }
+            out.push_str(&format!("<p class=\"meta\">Defined on line {}.</p>\n</section>\n", item.line));
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,267 @@
+// src/emit.rs
+//
+// Human-readable dumps of each compiler stage, for `--emit=tokens|ast|ir`.
+use crate::ast::*;
+use crate::bytecode::Program;
+use crate::codegen::IR;
+use crate::lexer::Token;
+use crate::span::{Span, Spanned};
+use std::collections::HashMap;
+
+/// The stage `--emit` stops after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Tokens,
+    Ast,
+    Ir,
+}
+
+impl Stage {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tokens" => Some(Stage::Tokens),
+            "ast" => Some(Stage::Ast),
+            "ir" => Some(Stage::Ir),
+            _ => None,
+        }
+    }
+}
+
+/// One token per line with its position and category.
+pub fn tokens(tokens: &[Spanned<Token>]) -> String {
+    let mut out = String::new();
+    for t in tokens {
+        let kind = match &t.node {
+            Token::Int(_) | Token::Float(_) => "number",
+            Token::Str(_) | Token::Interp(_) => "string",
+            Token::Ident(_) => "ident",
+            Token::True | Token::False => "boolean",
+            Token::StringType | Token::NumberType | Token::FloatType |
+            Token::BooleanType | Token::Void => "type",
+            Token::Plus | Token::Minus | Token::Star | Token::Slash | Token::Percent |
+            Token::Power | Token::EqEq | Token::BangEq | Token::Lt | Token::Gt |
+            Token::LtEq | Token::GtEq | Token::Eq => "operator",
+            Token::LParen | Token::RParen | Token::LBrace | Token::RBrace | Token::LBracket |
+            Token::RBracket | Token::Semicolon | Token::Comma | Token::Colon => "punct",
+            Token::Doc(_) => "doc",
+            Token::Eof => "eof",
+            _ => "keyword",
+        };
+        let at = format!("{}:{}", t.span.line, t.span.col);
+        out.push_str(&format!("{:>8}  {:<9} {}\n", at, kind, t.node));
+    }
+    out
+}
+
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: impl Into<String>, span: Span, children: Vec<Node>) -> Self {
+        Node { label: format!("{}  ({})", label.into(), span), children }
+    }
+
+    /// A grouping node such as `then` or `body`, which has no span of its own.
+    fn group(label: &str, children: Vec<Node>) -> Self {
+        Node { label: label.to_string(), children }
+    }
+
+    fn render(&self, prefix: &str, out: &mut String) {
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i + 1 == self.children.len();
+            out.push_str(&format!("{}{}{}\n", prefix, if last { "└─ " } else { "├─ " }, child.label));
+            child.render(&format!("{}{}", prefix, if last { "   " } else { "│  " }), out);
+        }
+    }
+}
+
+/// The AST as an indented tree.
+pub fn ast(program: &[Stmt]) -> String {
+    let root = Node::group("program", program.iter().map(stmt_node).collect());
+    let mut out = format!("{}\n", root.label);
+    root.render("", &mut out);
+    out
+}
+
+pub(crate) fn annotated(name: &str, ty: &Option<TypeAnn>) -> String {
+    match ty {
+        Some(ty) => format!("{}: {}", name, ty.node),
+        None => name.to_string(),
+    }
+}
+
+fn stmts_node(label: &str, body: &[Stmt]) -> Node {
+    Node::group(label, body.iter().map(stmt_node).collect())
+}
+
+fn stmt_node(s: &Stmt) -> Node {
+    let (label, children) = match &s.kind {
+        StmtKind::Const { name, ty, value } => (format!("constant {}", annotated(name, ty)), vec![expr_node(value)]),
+        StmtKind::Mutable { name, ty, init } => (format!("mutable {}", annotated(name, ty)), init.iter().map(expr_node).collect()),
+        StmtKind::Assign { name, value } => (format!("assign {}", name), vec![expr_node(value)]),
+        StmtKind::Expr(e) => ("expr".to_string(), vec![expr_node(e)]),
+        StmtKind::Return(value) => ("yield".to_string(), value.iter().map(expr_node).collect()),
+        StmtKind::Course { name, params, ret, body } | StmtKind::Purpose { name, params, ret, body } => {
+            let kind = if matches!(s.kind, StmtKind::Course { .. }) { "course" } else { "purpose" };
+            let params: Vec<String> = params.iter().map(|p| annotated(&p.name, &p.ty)).collect();
+            let ret = ret.as_ref().map(|r| format!(": {}", r.node)).unwrap_or_default();
+            (format!("{} {}({}){}", kind, name, params.join(", "), ret), body.iter().map(stmt_node).collect())
+        }
+        StmtKind::Persist { cond, body } => {
+            ("persist".to_string(), vec![Node::group("condition", vec![expr_node(cond)]), stmts_node("body", body)])
+        }
+        StmtKind::When { cond, then, elifs, otherwise } => {
+            let mut children = vec![Node::group("condition", vec![expr_node(cond)]), stmts_node("then", then)];
+            for (c, b) in elifs {
+                children.push(Node::group("differently", vec![
+                    Node::group("condition", vec![expr_node(c)]),
+                    stmts_node("then", b),
+                ]));
+            }
+            if !otherwise.is_empty() {
+                children.push(stmts_node("otherwise", otherwise));
+            }
+            ("when".to_string(), children)
+        }
+        StmtKind::Iterate { var, index, iterable, body } => {
+            let vars = match index {
+                Some(index) => format!("{}, {}", index, var),
+                None => var.clone(),
+            };
+            (format!("iterate {}", vars), vec![Node::group("across", vec![expr_node(iterable)]), stmts_node("body", body)])
+        }
+        StmtKind::Block(body) => ("block".to_string(), body.iter().map(stmt_node).collect()),
+    };
+    let children = match &s.doc {
+        Some(doc) => std::iter::once(Node::group(&format!("doc {:?}", doc), vec![])).chain(children).collect(),
+        None => children,
+    };
+    Node::new(label, s.span, children)
+}
+
+fn expr_node(e: &Expr) -> Node {
+    let (label, children) = match &e.kind {
+        ExprKind::Int(i) => (format!("int {}", i), vec![]),
+        ExprKind::Float(f) => (format!("float {}", f), vec![]),
+        ExprKind::Str(s) => (format!("string {:?}", s), vec![]),
+        ExprKind::Bool(b) => (format!("boolean {}", b), vec![]),
+        ExprKind::Ident(name) => (format!("ident {}", name), vec![]),
+        ExprKind::Interp(parts) => ("interpolation".to_string(), parts.iter().map(expr_node).collect()),
+        ExprKind::List(elements) => ("list".to_string(), elements.iter().map(expr_node).collect()),
+        ExprKind::Binary { left, op, right } => (format!("binary {}", op), vec![expr_node(left), expr_node(right)]),
+        ExprKind::Unary { op, expr } => (format!("unary {}", op), vec![expr_node(expr)]),
+        ExprKind::Call { callee, args } => (format!("call {}", callee), args.iter().map(expr_node).collect()),
+        ExprKind::Index { target, index, value } => {
+            let mut children = vec![expr_node(target), expr_node(index)];
+            children.extend(value.iter().map(|v| expr_node(v)));
+            (if value.is_some() { "index store" } else { "index" }.to_string(), children)
+        }
+    };
+    Node::new(label, e.span, children)
+}
+
+/// Disassembly of `program`: the function table, then every instruction
+/// with jump targets and function entries shown as labels.
+pub fn ir(program: &Program) -> String {
+    ir_from(program, 0)
+}
+
+/// Disassembly of the code appended to `program` from `start` on, such as
+/// one REPL entry.
+pub fn ir_from(program: &Program, start: usize) -> String {
+    let mut functions: Vec<_> = program.functions.iter().filter(|(_, f)| f.entry >= start).collect();
+    functions.sort_by_key(|(name, f)| (f.entry, name.as_str()));
+
+    let mut labels: HashMap<usize, String> = HashMap::new();
+    for (name, f) in &functions {
+        labels.insert(f.entry, name.to_string());
+    }
+    if let Some(IR::Jump(main)) = program.code.get(start) {
+        labels.entry(*main).or_insert_with(|| "main".to_string());
+    }
+    let mut targets: Vec<usize> = program.code[start..].iter().filter_map(|ir| match ir {
+        IR::Jump(t) | IR::JumpFalse(t) | IR::JumpTrue(t) | IR::IterNext(t, _) => Some(*t),
+        _ => None,
+    }).collect();
+    targets.sort();
+    targets.dedup();
+    let mut next = 0;
+    for t in targets {
+        labels.entry(t).or_insert_with(|| {
+            next += 1;
+            format!("L{}", next)
+        });
+    }
+
+    let mut out = String::new();
+    for (name, f) in &functions {
+        out.push_str(&format!("{} {} (arity {}, locals {})\n", f.kind, name, f.arity, f.locals));
+    }
+    if !functions.is_empty() {
+        out.push('\n');
+    }
+
+    let label = |t: &usize| labels.get(t).cloned().unwrap_or_else(|| t.to_string());
+    let global = |s: &u16| program.globals.get(*s as usize).cloned().unwrap_or_else(|| s.to_string());
+    let mut line = 0;
+    for (ip, ir) in program.code.iter().enumerate().skip(start) {
+        if let Some(name) = labels.get(&ip) {
+            out.push_str(&format!("{}:\n", name));
+        }
+        let (op, operands) = match ir {
+            IR::PushI(i) => ("push_int", i.to_string()),
+            IR::PushF(f) => ("push_float", f.to_string()),
+            IR::PushS(s) => ("push_str", format!("{:?}", s)),
+            IR::PushB(b) => ("push_bool", b.to_string()),
+            IR::PushNull => ("push_null", String::new()),
+            IR::LoadGlobal(s) => ("load_global", global(s)),
+            IR::StoreGlobal(s) => ("store_global", global(s)),
+            IR::LoadLocal(s) => ("load_local", s.to_string()),
+            IR::StoreLocal(s) => ("store_local", s.to_string()),
+            IR::Pop => ("pop", String::new()),
+            IR::Add => ("add", String::new()),
+            IR::Sub => ("sub", String::new()),
+            IR::Mul => ("mul", String::new()),
+            IR::Div => ("div", String::new()),
+            IR::Mod => ("mod", String::new()),
+            IR::Power => ("pow", String::new()),
+            IR::Neg => ("neg", String::new()),
+            IR::Eq => ("eq", String::new()),
+            IR::Neq => ("neq", String::new()),
+            IR::Lt => ("lt", String::new()),
+            IR::Gt => ("gt", String::new()),
+            IR::Le => ("le", String::new()),
+            IR::Ge => ("ge", String::new()),
+            IR::Not => ("not", String::new()),
+            IR::Jump(t) => ("jump", label(t)),
+            IR::JumpFalse(t) => ("jump_false", label(t)),
+            IR::JumpTrue(t) => ("jump_true", label(t)),
+            IR::Call(name, argc) => ("call", format!("{}, {}", name, argc)),
+            IR::CallNative(index, argc) => {
+                let name = program.natives.get(*index as usize).map_or("?", String::as_str);
+                ("call_native", format!("{}, {}", name, argc))
+            }
+            IR::Return => ("return", String::new()),
+            IR::MakeList(n) => ("make_list", n.to_string()),
+            IR::Concat(n) => ("concat", n.to_string()),
+            IR::GetIndex => ("get_index", String::new()),
+            IR::SetIndex => ("set_index", String::new()),
+            IR::IterInit => ("iter_init", String::new()),
+            IR::IterRange => ("iter_range", String::new()),
+            IR::IterNext(t, true) => ("iter_next", format!("{}, with index", label(t))),
+            IR::IterNext(t, false) => ("iter_next", label(t)),
+        };
+        let mut text = format!("  {:04}  {:<12} {}", ip, op, operands);
+        // Note the source line whenever it changes. Code with no source
+        // behind it, such as the jump to main, has line 0 and gets no note.
+        if let Some(span) = program.spans.get(ip).filter(|s| s.line != 0 && s.line != line) {
+            line = span.line;
+            text = format!("{:<40} ; line {}", text, line);
+        }
+        out.push_str(text.trim_end());
+        out.push('\n');
+    }
+    out
+}
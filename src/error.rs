@@ -1,17 +1,173 @@
 // src/error.rs
+use crate::codegen::FuncKind;
+use crate::span::Span;
+
+/// One active `course`/`purpose` call at the time a runtime error occurred.
+#[derive(Debug, Clone)]
+pub struct TraceFrame {
+    pub kind: FuncKind,
+    pub name: String,
+    pub call_site: Span,
+}
+
+impl std::fmt::Display for TraceFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "in {} `{}`, called at {}", self.kind, self.name, self.call_site)
+    }
+}
+
+/// What went wrong in the lexer, for callers that act on more than the
+/// message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexErrorKind {
+    /// A string, interpolation or block comment is missing its end.
+    Unterminated,
+    Other,
+}
+
 #[derive(Debug)]
 pub enum FluxError {
-    Lex(String),
-    Parse(String),
+    Lex { kind: LexErrorKind, message: String, span: Span, help: Option<String> },
+    Parse { message: String, span: Span, help: Option<String> },
+    /// Found by a static pass before the program runs. `labels` point at
+    /// related places, such as the declaration a write conflicts with.
+    Semantic {
+        code: &'static str,
+        message: String,
+        span: Span,
+        labels: Vec<(Span, String)>,
+        help: Option<String>,
+    },
+    /// Raised by the VM. `trace` lists active calls, innermost first.
+    Runtime { message: String, span: Span, trace: Vec<TraceFrame> },
+    /// A configured execution limit (steps, time, depth, size) was hit.
+    Limit { message: String, span: Span, trace: Vec<TraceFrame> },
+}
+
+impl FluxError {
+    pub fn lex(message: impl Into<String>, span: Span) -> Self {
+        FluxError::Lex { kind: LexErrorKind::Other, message: message.into(), span, help: None }
+    }
+
+    pub fn unterminated(message: impl Into<String>, span: Span) -> Self {
+        FluxError::Lex { kind: LexErrorKind::Unterminated, message: message.into(), span, help: None }
+    }
+
+    pub fn parse(message: impl Into<String>, span: Span) -> Self {
+        FluxError::Parse { message: message.into(), span, help: None }
+    }
+
+    /// A runtime error without a location; the VM fills in the instruction
+    /// that was running when a native function returns one.
+    pub fn runtime(message: impl Into<String>) -> Self {
+        FluxError::Runtime { message: message.into(), span: Span::default(), trace: vec![] }
+    }
+
+    pub fn semantic(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        FluxError::Semantic { code, message: message.into(), span, labels: vec![], help: None }
+    }
+
+    /// Attach a secondary label; only semantic errors carry them.
+    pub fn with_label(mut self, span: Span, text: impl Into<String>) -> Self {
+        if let FluxError::Semantic { labels, .. } = &mut self {
+            labels.push((span, text.into()));
+        }
+        self
+    }
+
+    pub fn with_help(mut self, text: impl Into<String>) -> Self {
+        match &mut self {
+            FluxError::Lex { help, .. } | FluxError::Parse { help, .. } |
+            FluxError::Semantic { help, .. } => *help = Some(text.into()),
+            FluxError::Runtime { .. } | FluxError::Limit { .. } => {}
+        }
+        self
+    }
+
+    /// Stable code shown as `error[E....]`. Never renumber an existing code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FluxError::Lex { .. } => "E0001",
+            FluxError::Parse { .. } => "E0002",
+            FluxError::Semantic { code, .. } => code,
+            FluxError::Runtime { .. } => "E0003",
+            FluxError::Limit { .. } => "E0004",
+        }
+    }
+
+    /// Process exit status for the CLI: 1 for compile errors, 2 for runtime
+    /// errors, 3 when an execution limit stopped the program.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            FluxError::Lex { .. } | FluxError::Parse { .. } | FluxError::Semantic { .. } => 1,
+            FluxError::Runtime { .. } => 2,
+            FluxError::Limit { .. } => 3,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            FluxError::Lex { message, .. } | FluxError::Parse { message, .. } |
+            FluxError::Semantic { message, .. } | FluxError::Runtime { message, .. } | FluxError::Limit { message, .. } => message,
+        }
+    }
+
+    /// Where the error happened; `None` for errors with no location, such
+    /// as a failed write once the program has finished.
+    pub fn span(&self) -> Option<Span> {
+        let span = match self {
+            FluxError::Lex { span, .. } | FluxError::Parse { span, .. } |
+            FluxError::Semantic { span, .. } | FluxError::Runtime { span, .. } | FluxError::Limit { span, .. } => *span,
+        };
+        Some(span).filter(|s| *s != Span::default())
+    }
+
+    pub fn lex_kind(&self) -> Option<LexErrorKind> {
+        match self {
+            FluxError::Lex { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        match self {
+            FluxError::Lex { help, .. } | FluxError::Parse { help, .. } |
+            FluxError::Semantic { help, .. } => help.as_deref(),
+            FluxError::Runtime { .. } | FluxError::Limit { .. } => None,
+        }
+    }
+
+    pub fn labels(&self) -> &[(Span, String)] {
+        match self {
+            FluxError::Semantic { labels, .. } => labels,
+            _ => &[],
+        }
+    }
+
+    pub fn trace(&self) -> &[TraceFrame] {
+        match self {
+            FluxError::Runtime { trace, .. } | FluxError::Limit { trace, .. } => trace,
+            _ => &[],
+        }
+    }
 }
 
 impl std::fmt::Display for FluxError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            FluxError::Lex(msg) => write!(f, "Lexer Error: {}", msg),
-            FluxError::Parse(msg) => write!(f, "Parser Error: {}", msg),
+            FluxError::Lex { message, span, .. } => write!(f, "Lexer Error at {}: {}", span, message),
+            FluxError::Parse { message, span, .. } => write!(f, "Parser Error at {}: {}", span, message),
+            FluxError::Semantic { code, message, span, .. } => write!(f, "Error {} at {}: {}", code, span, message),
+            FluxError::Runtime { message, span, trace } | FluxError::Limit { message, span, trace } => {
+                let kind = if matches!(self, FluxError::Limit { .. }) { "Limit Exceeded" } else { "Runtime Error" };
+                write!(f, "{} at {}: {}", kind, span, message)?;
+                for frame in trace {
+                    write!(f, "\n    {}", frame)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-impl std::error::Error for FluxError {}
\ No newline at end of file
+impl std::error::Error for FluxError {}
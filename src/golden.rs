@@ -0,0 +1,199 @@
+// src/golden.rs
+//
+// Golden-file tests for `flux test <dir>`. Each `.fl` file states what it
+// should do in comments, and the runner compiles and runs it to compare:
+//
+//   // expect: text            a line of stdout, in order (trailing spaces ignored)
+//   // expect-error: E0003 line 7
+//                              an error with that code, optionally at that line
+//   // expect-exit: 2          the exit code, if not implied by the errors
+//   // input: text             a line fed to `getInput`
+use crate::vm::{Limits, VM};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// What a file expects, collected from its comments.
+#[derive(Default)]
+struct Expectations {
+    stdout: Vec<String>,
+    errors: Vec<(String, Option<usize>)>,
+    exit: Option<i32>,
+    input: String,
+}
+
+impl Expectations {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut expect = Expectations::default();
+        for (n, line) in source.lines().enumerate() {
+            let Some((_, comment)) = line.split_once("//") else { continue };
+            let comment = comment.trim_start();
+            let Some((key, value)) = comment.split_once(':') else { continue };
+            let value = value.strip_prefix(' ').unwrap_or(value).trim_end();
+            match key {
+                "expect" => expect.stdout.push(value.to_string()),
+                "expect-error" => {
+                    let mut words = value.split_whitespace();
+                    let code = words.next().unwrap_or_default().to_string();
+                    let line = match (words.next(), words.next(), words.next()) {
+                        (None, _, _) => None,
+                        (Some("line"), Some(l), None) => Some(l.parse().map_err(|_| format!("line {}: bad line number `{}`", n + 1, l))?),
+                        _ => return Err(format!("line {}: expected `// expect-error: E0000 line N`", n + 1)),
+                    };
+                    expect.errors.push((code, line));
+                }
+                "expect-exit" => {
+                    expect.exit = Some(value.parse().map_err(|_| format!("line {}: bad exit code `{}`", n + 1, value))?);
+                }
+                "input" => {
+                    expect.input.push_str(value);
+                    expect.input.push('\n');
+                }
+                _ => {}
+            }
+        }
+        Ok(expect)
+    }
+
+    /// The exit code the CLI would give: the worst expected error's, or 0.
+    fn exit_code(&self) -> i32 {
+        self.exit.unwrap_or_else(|| self.errors.iter().map(|(code, _)| match code.as_str() {
+            "E0003" => 2,
+            "E0004" => 3,
+            _ => 1,
+        }).max().unwrap_or(0))
+    }
+}
+
+/// The result of one golden file.
+pub struct Outcome {
+    pub path: PathBuf,
+    /// Why the file failed, one entry per mismatch; empty if it passed.
+    pub failures: Vec<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", if self.passed() { "PASS" } else { "FAIL" }, self.path.display())?;
+        for failure in &self.failures {
+            for line in failure.lines() {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Line diff of `expected` against `actual`: `-` marks a missing line and
+/// `+` an unexpected one.
+fn diff(expected: &[String], actual: &[String]) -> String {
+    let (n, m) = (expected.len(), actual.len());
+    // lcs[i][j]: longest common subsequence of expected[i..] and actual[j..].
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if expected[i] == actual[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j, mut out) = (0, 0, String::new());
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            out.push_str(&format!("  {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            out.push_str(&format!("+ {}\n", actual[j]));
+            j += 1;
+        } else {
+            out.push_str(&format!("- {}\n", expected[i]));
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Compile and run the file at `path` and compare it with its expectations.
+pub fn run_file(path: &Path) -> Outcome {
+    let mut outcome = Outcome { path: path.to_path_buf(), failures: vec![] };
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            outcome.failures.push(format!("couldn't read the file: {}", e));
+            return outcome;
+        }
+    };
+    let expect = match Expectations::parse(&source) {
+        Ok(expect) => expect,
+        Err(e) => {
+            outcome.failures.push(format!("bad expectation on {}", e));
+            return outcome;
+        }
+    };
+
+    let (stdout, errors) = match crate::compile(&path.to_string_lossy(), &source) {
+        Ok(program) => {
+            // A runaway test shouldn't hang the whole suite.
+            let mut vm = VM::with_limits(Limits { timeout: Some(Duration::from_secs(10)), ..Limits::default() });
+            let (stdout, result) = vm.run_captured(&program, &expect.input);
+            (stdout, result.err().into_iter().collect())
+        }
+        Err(errors) => (String::new(), errors),
+    };
+
+    let actual: Vec<String> = stdout.lines().map(|l| l.trim_end().to_string()).collect();
+    if actual != expect.stdout {
+        outcome.failures.push(format!("stdout differs (- expected, + actual):\n{}", diff(&expect.stdout, &actual)));
+    }
+
+    let mut unmatched: Vec<_> = errors.iter().collect();
+    for (code, line) in &expect.errors {
+        let found = unmatched.iter().position(|e| {
+            e.code() == code && line.is_none_or(|l| e.span().is_some_and(|s| s.line == l))
+        });
+        match found {
+            Some(i) => { unmatched.remove(i); }
+            None => outcome.failures.push(match line {
+                Some(line) => format!("missing error: {} line {}", code, line),
+                None => format!("missing error: {}", code),
+            }),
+        }
+    }
+    for e in unmatched {
+        let at = e.span().map(|s| format!(" line {}", s.line)).unwrap_or_default();
+        outcome.failures.push(format!("unexpected error: {}{}: {}", e.code(), at, e.message()));
+    }
+
+    let exit = errors.iter().map(|e| e.exit_code()).max().unwrap_or(0);
+    if exit != expect.exit_code() {
+        outcome.failures.push(format!("exit code: expected {}, found {}", expect.exit_code(), exit));
+    }
+    outcome
+}
+
+/// Run every `.fl` file under `dir`, in path order.
+pub fn run_dir(dir: &Path) -> io::Result<Vec<Outcome>> {
+    let mut files = vec![];
+    collect(dir, &mut files)?;
+    files.sort();
+    Ok(files.iter().map(|f| run_file(f)).collect())
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "fl") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 // src/lexer.rs
-use crate::error::FluxError;
+use crate::error::{FluxError, LexErrorKind};
+use crate::span::{Span, Spanned};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -8,35 +9,140 @@ pub enum Token {
     Iterate, Across, To, // ADDED: To
     And, Or, Not, Void,
     StringType, NumberType, FloatType, BooleanType,
-    Int(i64), Float(f64), Str(String), Ident(String), True, False,
+    /// Up to 2^63, which only fits once negated: the parser folds
+    /// `-9223372036854775808` and rejects it anywhere else.
+    Int(u64), Float(f64), Str(String), Ident(String), True, False,
+    /// A string with `{expression}`s in it.
+    Interp(Vec<StrPart>),
+    /// One line of a `///` comment, without the slashes.
+    Doc(String),
     Plus, Minus, Star, Slash, Percent, Power,
     EqEq, BangEq, Lt, Gt, LtEq, GtEq, Eq,
-    LParen, RParen, LBrace, RBrace, LBracket, RBracket, Semicolon, Comma,
-    EOF, // REMOVED: DotDot
+    LParen, RParen, LBrace, RBrace, LBracket, RBracket, Semicolon, Comma, Colon,
+    Eof, // REMOVED: DotDot
+}
+
+/// A piece of an interpolated string: literal text, or the tokens of an
+/// embedded expression, ending in `Eof`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StrPart {
+    Text(String),
+    Expr(Vec<Spanned<Token>>),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let text = match self {
+            Token::Int(i) => return write!(f, "`{}`", i),
+            Token::Float(v) => return write!(f, "`{}`", v),
+            Token::Str(s) => return write!(f, "`{:?}`", s),
+            Token::Interp(parts) => {
+                let text: String = parts.iter().map(|p| match p {
+                    StrPart::Text(t) => t.escape_debug().to_string().replace('{', "\\{"),
+                    StrPart::Expr(_) => "{…}".to_string(),
+                }).collect();
+                return write!(f, "`\"{}\"`", text);
+            }
+            Token::Ident(name) => return write!(f, "`{}`", name),
+            Token::Doc(text) => return write!(f, "`/// {}`", text),
+            Token::Eof => return write!(f, "end of file"),
+            Token::Constant => "constant", Token::Mutable => "mutable", Token::Assign => "assign",
+            Token::Yield => "yield", Token::Course => "course", Token::Purpose => "purpose",
+            Token::When => "when", Token::Then => "then", Token::Persist => "persist",
+            Token::Differently => "differently", Token::Otherwise => "otherwise",
+            Token::Iterate => "iterate", Token::Across => "across", Token::To => "to",
+            Token::And => "and", Token::Or => "or", Token::Not => "not", Token::Void => "void",
+            Token::StringType => "string", Token::NumberType => "number",
+            Token::FloatType => "float", Token::BooleanType => "boolean",
+            Token::True => "true", Token::False => "false",
+            Token::Plus => "+", Token::Minus => "-", Token::Star => "*", Token::Slash => "/",
+            Token::Percent => "%", Token::Power => "**",
+            Token::EqEq => "==", Token::BangEq => "!=", Token::Lt => "<", Token::Gt => ">",
+            Token::LtEq => "<=", Token::GtEq => ">=", Token::Eq => "=",
+            Token::LParen => "(", Token::RParen => ")", Token::LBrace => "{", Token::RBrace => "}",
+            Token::LBracket => "[", Token::RBracket => "]", Token::Semicolon => ";", Token::Comma => ",",
+            Token::Colon => ":",
+        };
+        write!(f, "`{}`", text)
+    }
+}
+
+const ESCAPES: &str = "valid escapes are `\\n`, `\\t`, `\\r`, `\\0`, `\\\"`, `\\\\`, `\\{`, `\\}` and `\\u{...}`";
+
+/// The error for an integer literal outside the range of `number`.
+pub(crate) fn too_large(literal: &str, span: Span) -> FluxError {
+    FluxError::lex(format!("Integer literal `{}` is too large", literal), span)
+        .with_help("a `number` ranges from -9223372036854775808 to 9223372036854775807; use a float for larger values")
+}
+
+/// Check that every `_` in `literal` sits between two digits of one of its
+/// digit `runs`.
+fn check_separators(literal: &str, runs: &[&str], span: Span) -> Result<(), FluxError> {
+    if runs.iter().any(|run| run.starts_with('_') || run.ends_with('_')) {
+        return Err(FluxError::lex(format!("Misplaced `_` in `{}`", literal), span)
+            .with_help("`_` can only separate digits, as in `1_000_000`"));
+    }
+    Ok(())
+}
+
+/// Position of the first byte of a token, captured before lexing it.
+#[derive(Clone, Copy)]
+struct Mark {
+    pos: usize,
+    line: usize,
+    col: usize,
 }
 
 pub struct Lexer<'a> {
     input: &'a [u8],
     pos: usize,
+    line: usize,
+    line_start: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(source: &'a str) -> Self {
-        Self { input: source.as_bytes(), pos: 0 }
+        Self { input: source.as_bytes(), pos: 0, line: 1, line_start: 0 }
     }
 
-    fn advance(&mut self) { self.pos += 1; }
+    fn advance(&mut self) {
+        if self.cur() == b'\n' {
+            self.line += 1;
+            self.line_start = self.pos + 1;
+        }
+        self.pos += 1;
+    }
     fn cur(&self) -> u8 { self.input.get(self.pos).copied().unwrap_or(0) }
     fn peek(&self) -> u8 { self.input.get(self.pos + 1).copied().unwrap_or(0) }
 
-    fn skip_whitespace(&mut self) {
+    fn mark(&self) -> Mark {
+        // Columns count characters, not bytes, so multi-byte UTF-8 lines stay aligned.
+        let col = String::from_utf8_lossy(&self.input[self.line_start..self.pos]).chars().count() + 1;
+        Mark { pos: self.pos, line: self.line, col }
+    }
+
+    fn span_from(&self, mark: Mark) -> Span {
+        Span::new(mark.pos, self.pos, mark.line, mark.col)
+    }
+
+    /// Skip whitespace and comments, stopping at a doc comment, which is a
+    /// token of its own.
+    fn skip_whitespace(&mut self) -> Result<(), FluxError> {
         while self.pos < self.input.len() {
             match self.cur() {
                 b' ' | b'\t' | b'\n' | b'\r' => self.advance(),
+                b'/' if self.at_doc_comment() => break,
                 b'/' if self.peek() == b'/' => self.skip_line_comment(),
+                b'/' if self.peek() == b'*' => self.skip_block_comment()?,
                 _ => break,
             }
         }
+        Ok(())
+    }
+
+    /// `///` starts a doc comment; `////` and beyond is an ordinary one.
+    fn at_doc_comment(&self) -> bool {
+        self.at(b"///") && !self.at(b"////")
     }
 
     fn skip_line_comment(&mut self) {
@@ -45,155 +151,438 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    pub fn lex(mut self) -> Result<Vec<Token>, FluxError> {
-        let mut tokens = Vec::with_capacity(self.input.len() / 6);
-        
-        while self.pos < self.input.len() {
-            self.skip_whitespace();
-            if self.pos >= self.input.len() { break; }
+    /// A `/* ... */` comment, which may contain other block comments.
+    fn skip_block_comment(&mut self) -> Result<(), FluxError> {
+        let mark = self.mark();
+        self.advance();
+        self.advance();
+        let open = self.span_from(mark);
+        let mut depth = 1;
+        while depth > 0 {
+            if self.pos >= self.input.len() {
+                return Err(FluxError::unterminated("Unterminated block comment", open)
+                    .with_help("close it with `*/`; block comments nest, so each `/*` inside needs its own `*/`"));
+            }
+            if self.at(b"/*") || self.at(b"*/") {
+                depth += if self.cur() == b'/' { 1 } else { -1 };
+                self.advance();
+            }
+            self.advance();
+        }
+        Ok(())
+    }
 
+    /// A `///` comment, up to the end of its line.
+    fn doc_comment(&mut self) -> Token {
+        for _ in 0..3 {
+            self.advance();
+        }
+        let start = self.pos;
+        self.skip_line_comment();
+        let text = std::str::from_utf8(&self.input[start..self.pos]).expect("source is UTF-8");
+        Token::Doc(text.strip_prefix(' ').unwrap_or(text).trim_end().to_string())
+    }
+
+    fn at(&self, text: &[u8]) -> bool {
+        self.input[self.pos..].starts_with(text)
+    }
+
+    /// A `"..."` string, or a `"""..."""` one that may span lines, starting
+    /// at its opening quote. A newline right after `"""` is not part of it.
+    fn string(&mut self, mark: Mark) -> Result<Token, FluxError> {
+        let triple = self.at(b"\"\"\"");
+        for _ in 0..if triple { 3 } else { 1 } {
+            self.advance();
+        }
+        if triple && self.at(b"\r\n") {
+            self.advance();
+        }
+        if triple && self.cur() == b'\n' {
+            self.advance();
+        }
+        let mut text = Vec::new();
+        let mut parts = vec![];
+        loop {
+            if self.pos >= self.input.len() || (!triple && self.cur() == b'\n') {
+                let err = FluxError::unterminated("Unterminated string", self.span_from(mark));
+                return Err(if triple {
+                    err.with_help("close it with `\"\"\"`")
+                } else {
+                    err.with_help("close it with `\"`; use `\"\"\"` for a string that spans lines")
+                });
+            }
             match self.cur() {
-                b'0'..=b'9' => {
-                    let start = self.pos;
-                    while matches!(self.cur(), b'0'..=b'9') { 
-                        self.advance(); 
-                    }
-                    
-                    if self.cur() == b'.' && matches!(self.peek(), b'0'..=b'9') {
+                b'"' if !triple => {
+                    self.advance();
+                    break;
+                }
+                b'"' if self.at(b"\"\"\"") => {
+                    for _ in 0..3 {
                         self.advance();
-                        while matches!(self.cur(), b'0'..=b'9') { 
-                            self.advance(); 
-                        }
                     }
-                    
-                    let s = std::str::from_utf8(&self.input[start..self.pos])
-                        .map_err(|e| FluxError::Lex(format!("Invalid UTF-8: {}", e)))?;
-                    
-                    if s.contains('.') {
-                        let f = s.parse().map_err(|_| FluxError::Lex(format!("Invalid float: {}", s)))?;
-                        tokens.push(Token::Float(f));
-                    } else {
-                        let i = s.parse().map_err(|_| FluxError::Lex(format!("Invalid integer: {}", s)))?;
-                        tokens.push(Token::Int(i));
+                    break;
+                }
+                b'\\' => {
+                    let c = self.escape()?;
+                    text.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                b'{' => {
+                    if !text.is_empty() {
+                        parts.push(StrPart::Text(String::from_utf8(std::mem::take(&mut text)).expect("source is UTF-8")));
                     }
+                    parts.push(self.interpolation(triple)?);
                 }
-                b'"' => {
+                b => {
+                    text.push(b);
                     self.advance();
-                    let start = self.pos;
-                    while self.pos < self.input.len() && self.cur() != b'"' {
-                        self.advance();
-                    }
-                    let s = String::from_utf8_lossy(&self.input[start..self.pos]).to_string();
-                    tokens.push(Token::Str(s));
-                    if self.cur() == b'"' {
-                        self.advance();
-                    }
                 }
-                b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
-                    let start = self.pos;
-                    while self.pos < self.input.len() && matches!(self.cur(), b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_') {
-                        self.advance();
-                    }
-                    let word = std::str::from_utf8(&self.input[start..self.pos])
-                        .map_err(|e| FluxError::Lex(format!("Invalid UTF-8: {}", e)))?;
-                    let token = match word {
-                        "constant" => Token::Constant,
-                        "mutable" => Token::Mutable,
-                        "assign" => Token::Assign,
-                        "yield" => Token::Yield,        // ADDED
-                        "course" => Token::Course,
-                        "purpose" => Token::Purpose,
-                        "when" => Token::When,
-                        "then" => Token::Then,
-                        "persist" => Token::Persist,
-                        "differently" => Token::Differently,
-                        "otherwise" => Token::Otherwise,
-                        "iterate" => Token::Iterate,
-                        "across" => Token::Across,
-                        "to" => Token::To,              // ADDED
-                        "and" => Token::And,
-                        "or" => Token::Or,
-                        "not" => Token::Not,
-                        "true" => Token::True,
-                        "false" => Token::False,
-                        "string" => Token::StringType,
-                        "number" => Token::NumberType,
-                        "float" => Token::FloatType,
-                        "boolean" => Token::BooleanType,
-                        "void" => Token::Void,
-                        _ => Token::Ident(word.to_string()),
-                    };
-                    tokens.push(token);
+            }
+        }
+        let text = String::from_utf8(text).expect("source is UTF-8");
+        if parts.is_empty() {
+            return Ok(Token::Str(text));
+        }
+        if !text.is_empty() {
+            parts.push(StrPart::Text(text));
+        }
+        Ok(Token::Interp(parts))
+    }
+
+    /// The `{expression}` in a string, starting at its `{`. In a one-line
+    /// string the expression must close on the same line.
+    fn interpolation(&mut self, triple: bool) -> Result<StrPart, FluxError> {
+        let open = self.mark();
+        self.advance();
+        let brace = self.span_from(open);
+        let unterminated = || FluxError::unterminated("Unterminated interpolation", brace)
+            .with_help("close it with `}`, or write `\\{` for a literal brace");
+        let mut tokens = vec![];
+        let mut depth = 0;
+        loop {
+            self.skip_whitespace()?;
+            if self.pos >= self.input.len() || (!triple && self.line != open.line) {
+                return Err(unterminated());
+            }
+            match self.cur() {
+                b'}' if depth == 0 => break,
+                b'{' => depth += 1,
+                b'}' => depth -= 1,
+                _ => {}
+            }
+            let mark = self.mark();
+            // The string's closing quote read as the start of another string.
+            let quote = self.cur() == b'"';
+            let token = self.token(mark).map_err(|e| match e.lex_kind() {
+                Some(LexErrorKind::Unterminated) if quote => unterminated(),
+                _ => e,
+            })?;
+            tokens.push(Spanned::new(token, self.span_from(mark)));
+        }
+        let close = self.mark();
+        self.advance();
+        if tokens.is_empty() {
+            return Err(FluxError::lex("Empty interpolation", self.span_from(open))
+                .with_help("put an expression between the braces, or write `\\{` for a literal brace"));
+        }
+        tokens.push(Spanned::new(Token::Eof, self.span_from(close)));
+        Ok(StrPart::Expr(tokens))
+    }
+
+    /// The character an escape sequence stands for, starting at its `\`.
+    fn escape(&mut self) -> Result<char, FluxError> {
+        let mark = self.mark();
+        self.advance();
+        let c = match self.cur() {
+            b'n' => '\n',
+            b't' => '\t',
+            b'r' => '\r',
+            b'0' => '\0',
+            b'"' => '"',
+            b'\\' => '\\',
+            b'{' => '{',
+            b'}' => '}',
+            b'u' => {
+                self.advance();
+                return self.unicode_escape(mark);
+            }
+            _ if self.pos >= self.input.len() || self.cur() == b'\n' => {
+                return Err(FluxError::lex("Expected an escape sequence after `\\`", self.span_from(mark))
+                    .with_help(ESCAPES));
+            }
+            _ => {
+                self.advance();
+                while self.cur() & 0xC0 == 0x80 { self.advance(); }
+                let seq = String::from_utf8_lossy(&self.input[mark.pos..self.pos]).into_owned();
+                return Err(FluxError::lex(format!("Unknown escape sequence `{}`", seq), self.span_from(mark))
+                    .with_help(format!("{}; for a literal backslash write `\\\\` or use a raw string `r\"...\"`", ESCAPES)));
+            }
+        };
+        self.advance();
+        Ok(c)
+    }
+
+    /// The rest of a `\u{...}` escape, after the `u`.
+    fn unicode_escape(&mut self, mark: Mark) -> Result<char, FluxError> {
+        const HELP: &str = "write a code point as 1 to 6 hex digits, e.g. `\\u{1F600}`";
+        if self.cur() != b'{' {
+            return Err(FluxError::lex("Expected `{` after `\\u`", self.span_from(mark)).with_help(HELP));
+        }
+        self.advance();
+        let start = self.pos;
+        while self.cur().is_ascii_hexdigit() {
+            self.advance();
+        }
+        let digits = std::str::from_utf8(&self.input[start..self.pos]).expect("hex digits are ASCII").to_string();
+        if self.cur() != b'}' {
+            return Err(FluxError::lex("Unterminated unicode escape", self.span_from(mark)).with_help(HELP));
+        }
+        self.advance();
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(FluxError::lex("Unicode escape needs 1 to 6 hex digits", self.span_from(mark)).with_help(HELP));
+        }
+        u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32).ok_or_else(|| {
+            FluxError::lex(format!("`\\u{{{}}}` is not a valid Unicode character", digits), self.span_from(mark))
+        })
+    }
+
+    /// A raw string, `r"..."` or `r#"..."#` with any number of `#`s, taken
+    /// exactly as written.
+    fn raw_string(&mut self, mark: Mark) -> Result<Token, FluxError> {
+        self.advance();
+        let mut hashes = 0;
+        while self.cur() == b'#' {
+            hashes += 1;
+            self.advance();
+        }
+        if self.cur() != b'"' {
+            return Err(FluxError::lex("Expected `\"` to start a raw string", self.span_from(mark)));
+        }
+        self.advance();
+        let close = format!("\"{}", "#".repeat(hashes));
+        let start = self.pos;
+        while !self.at(close.as_bytes()) {
+            if self.pos >= self.input.len() {
+                return Err(FluxError::unterminated("Unterminated raw string", self.span_from(mark))
+                    .with_help(format!("close it with `{}`", close)));
+            }
+            self.advance();
+        }
+        let text = String::from_utf8_lossy(&self.input[start..self.pos]).into_owned();
+        for _ in 0..close.len() {
+            self.advance();
+        }
+        Ok(Token::Str(text))
+    }
+
+    pub fn lex(mut self) -> Result<Vec<Spanned<Token>>, FluxError> {
+        let mut tokens = Vec::with_capacity(self.input.len() / 6);
+        
+        while self.pos < self.input.len() {
+            self.skip_whitespace()?;
+            if self.pos >= self.input.len() { break; }
+
+            let mark = self.mark();
+            let token = self.token(mark)?;
+            tokens.push(Spanned::new(token, self.span_from(mark)));
+        }
+        let eof = self.mark();
+        tokens.push(Spanned::new(Token::Eof, self.span_from(eof)));
+        Ok(tokens)
+    }
+
+    /// Skip digits of `radix` and `_` separators.
+    fn digits(&mut self, radix: u32) {
+        while self.cur() == b'_' || (self.cur() as char).is_digit(radix) {
+            self.advance();
+        }
+    }
+
+    /// A number: decimal with an optional fraction and exponent, or a `0x`,
+    /// `0o` or `0b` integer. `_` can separate digits.
+    fn number(&mut self, mark: Mark) -> Result<Token, FluxError> {
+        let radix = match (self.cur(), self.peek()) {
+            (b'0', b'x' | b'X') => Some((16, "hexadecimal")),
+            (b'0', b'o' | b'O') => Some((8, "octal")),
+            (b'0', b'b' | b'B') => Some((2, "binary")),
+            _ => None,
+        };
+        if let Some((radix, name)) = radix {
+            self.advance();
+            self.advance();
+            // Take every letter and digit so `0b102` is one bad literal, not two tokens.
+            while self.cur().is_ascii_alphanumeric() || self.cur() == b'_' {
+                self.advance();
+            }
+            let literal = self.text(mark);
+            let span = self.span_from(mark);
+            let (prefix, digits) = literal.split_at(2);
+            if let Some(bad) = digits.chars().find(|&c| c != '_' && !c.is_digit(radix)) {
+                return Err(FluxError::lex(format!("Invalid digit `{}` in {} literal `{}`", bad, name, literal), span));
+            }
+            if digits.is_empty() {
+                return Err(FluxError::lex(format!("Expected {} digits after `{}`", name, prefix), span));
+            }
+            check_separators(&literal, &[digits], span)?;
+            return match u64::from_str_radix(&digits.replace('_', ""), radix) {
+                Ok(i) if i <= i64::MIN.unsigned_abs() => Ok(Token::Int(i)),
+                _ => Err(too_large(&literal, span)),
+            };
+        }
+
+        self.digits(10);
+        let mut float = false;
+        if self.cur() == b'.' && self.peek().is_ascii_digit() {
+            float = true;
+            self.advance();
+            self.digits(10);
+        }
+        let sign = matches!(self.peek(), b'+' | b'-') as usize;
+        if matches!(self.cur(), b'e' | b'E') && self.input.get(self.pos + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            float = true;
+            for _ in 0..1 + sign {
+                self.advance();
+            }
+            self.digits(10);
+        }
+        let literal = self.text(mark);
+        let span = self.span_from(mark);
+        check_separators(&literal, &literal.split(['.', 'e', 'E', '+', '-']).collect::<Vec<_>>(), span)?;
+        let digits = literal.replace('_', "");
+        if float {
+            return match digits.parse::<f64>() {
+                Ok(f) if f.is_finite() => Ok(Token::Float(f)),
+                _ => Err(FluxError::lex(format!("Float literal `{}` is too large", literal), span)
+                    .with_help("a `float` can be at most about 1.8e308")),
+            };
+        }
+        match digits.parse::<u64>() {
+            Ok(i) if i <= i64::MIN.unsigned_abs() => Ok(Token::Int(i)),
+            _ => Err(too_large(&literal, span)),
+        }
+    }
+
+    /// The source text from `mark` to the current position.
+    fn text(&self, mark: Mark) -> String {
+        String::from_utf8_lossy(&self.input[mark.pos..self.pos]).into_owned()
+    }
+
+    /// The token starting at `mark`, which is the current position.
+    fn token(&mut self, mark: Mark) -> Result<Token, FluxError> {
+        let token = match self.cur() {
+            b'0'..=b'9' => self.number(mark)?,
+            b'"' => self.string(mark)?,
+            b'r' if matches!(self.peek(), b'"' | b'#') => self.raw_string(mark)?,
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                let start = self.pos;
+                while self.pos < self.input.len() && matches!(self.cur(), b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_') {
+                    self.advance();
                 }
-                b'+' => { tokens.push(Token::Plus); self.advance(); }
-                b'-' => { tokens.push(Token::Minus); self.advance(); }
-                b'*' => { 
-                    self.advance(); 
-                    if self.cur() == b'*' { 
-                        self.advance(); 
-                        tokens.push(Token::Power); 
-                    } else { 
-                        tokens.push(Token::Star); 
-                    } 
+                let word = std::str::from_utf8(&self.input[start..self.pos])
+                    .map_err(|e| FluxError::lex(format!("Invalid UTF-8: {}", e), self.span_from(mark)))?;
+                match word {
+                    "constant" => Token::Constant,
+                    "mutable" => Token::Mutable,
+                    "assign" => Token::Assign,
+                    "yield" => Token::Yield,        // ADDED
+                    "course" => Token::Course,
+                    "purpose" => Token::Purpose,
+                    "when" => Token::When,
+                    "then" => Token::Then,
+                    "persist" => Token::Persist,
+                    "differently" => Token::Differently,
+                    "otherwise" => Token::Otherwise,
+                    "iterate" => Token::Iterate,
+                    "across" => Token::Across,
+                    "to" => Token::To,              // ADDED
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "true" => Token::True,
+                    "false" => Token::False,
+                    "string" => Token::StringType,
+                    "number" => Token::NumberType,
+                    "float" => Token::FloatType,
+                    "boolean" => Token::BooleanType,
+                    "void" => Token::Void,
+                    _ => Token::Ident(word.to_string()),
                 }
-                b'/' => { tokens.push(Token::Slash); self.advance(); }
-                b'%' => { tokens.push(Token::Percent); self.advance(); }
-                b'=' => { 
+            }
+            b'+' => { self.advance(); Token::Plus }
+            b'-' => { self.advance(); Token::Minus }
+            b'*' => { 
+                self.advance(); 
+                if self.cur() == b'*' { 
                     self.advance(); 
-                    if self.cur() == b'=' { 
-                        self.advance(); 
-                        tokens.push(Token::EqEq); 
-                    } else { 
-                        tokens.push(Token::Eq); 
-                    } 
-                }
-                b'!' => { 
+                    Token::Power
+                } else { 
+                    Token::Star
+                } 
+            }
+            b'/' if self.at_doc_comment() => self.doc_comment(),
+            b'/' => { self.advance(); Token::Slash }
+            b'%' => { self.advance(); Token::Percent }
+            b'=' => { 
+                self.advance(); 
+                if self.cur() == b'=' { 
                     self.advance(); 
-                    if self.cur() == b'=' { 
-                        self.advance(); 
-                        tokens.push(Token::BangEq); 
-                    } else { 
-                        tokens.push(Token::Not); 
-                    } 
-                }
-                b'<' => { 
+                    Token::EqEq
+                } else { 
+                    Token::Eq
+                } 
+            }
+            b'!' => { 
+                self.advance(); 
+                if self.cur() == b'=' { 
                     self.advance(); 
-                    if self.cur() == b'=' { 
-                        self.advance(); 
-                        tokens.push(Token::LtEq); 
-                    } else { 
-                        tokens.push(Token::Lt); 
-                    } 
-                }
-                b'>' => { 
+                    Token::BangEq
+                } else { 
+                    Token::Not
+                } 
+            }
+            b'<' => { 
+                self.advance(); 
+                if self.cur() == b'=' { 
                     self.advance(); 
-                    if self.cur() == b'=' { 
-                        self.advance(); 
-                        tokens.push(Token::GtEq); 
-                    } else { 
-                        tokens.push(Token::Gt); 
-                    } 
-                }
-                b'[' => { tokens.push(Token::LBracket); self.advance(); }
-                b']' => { tokens.push(Token::RBracket); self.advance(); }
-                b'(' => { tokens.push(Token::LParen); self.advance(); }
-                b')' => { tokens.push(Token::RParen); self.advance(); }
-                b'{' => { tokens.push(Token::LBrace); self.advance(); }
-                b'}' => { tokens.push(Token::RBrace); self.advance(); }
-                b';' => { tokens.push(Token::Semicolon); self.advance(); }
-                b',' => { tokens.push(Token::Comma); self.advance(); }
-                b'.' => { 
-                    // Single dot is invalid now that we removed DotDot
-                    return Err(FluxError::Lex("Invalid token: single '.'".to_string()));
-                }
-                ch => {
-                    let ch = ch as char;
-                    self.advance();
-                    return Err(FluxError::Lex(format!("Unexpected character: '{}'", ch)));
+                    Token::LtEq
+                } else { 
+                    Token::Lt
+                } 
+            }
+            b'>' => { 
+                self.advance(); 
+                if self.cur() == b'=' { 
+                    self.advance(); 
+                    Token::GtEq
+                } else { 
+                    Token::Gt
+                } 
+            }
+            b'[' => { self.advance(); Token::LBracket }
+            b']' => { self.advance(); Token::RBracket }
+            b'(' => { self.advance(); Token::LParen }
+            b')' => { self.advance(); Token::RParen }
+            b'{' => { self.advance(); Token::LBrace }
+            b'}' => { self.advance(); Token::RBrace }
+            b';' => { self.advance(); Token::Semicolon }
+            b',' => { self.advance(); Token::Comma }
+            b':' => { self.advance(); Token::Colon }
+            b'.' => { 
+                // Single dot is invalid now that we removed DotDot
+                self.advance();
+                let err = FluxError::lex("Invalid token: single '.'", self.span_from(mark));
+                if self.cur().is_ascii_digit() {
+                    return Err(err.with_help("write decimals with a leading digit, e.g. `0.5`"));
                 }
+                return Err(err);
             }
-        }
-        tokens.push(Token::EOF);
-        Ok(tokens)
+            _ => {
+                // Decode the full character so multi-byte input is reported whole.
+                self.advance();
+                while self.cur() & 0xC0 == 0x80 { self.advance(); }
+                let ch = String::from_utf8_lossy(&self.input[mark.pos..self.pos]).chars().next().unwrap_or('?');
+                return Err(FluxError::lex(format!("Unexpected character: '{}'", ch), self.span_from(mark)));
+            }
+        };
+        Ok(token)
     }
-}
\ No newline at end of file
+}
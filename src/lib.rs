@@ -0,0 +1,89 @@
+// src/lib.rs
+//
+// Flux as a library: compile source to a `Program`, run it on a `VM` and
+// read results back as `Value`s. Failures come back as `FluxError`s;
+// turning them into text is up to the caller, for example with `Renderer`.
+mod ast;
+mod lexer;
+mod parser;
+mod codegen;
+mod vm;
+mod error;
+mod span;
+mod diagnostic;
+mod resolver;
+mod semantic;
+mod types;
+mod bytecode;
+mod emit;
+mod natives;
+mod doc;
+pub mod repl;
+pub mod golden;
+
+pub use bytecode::{LoadError, Program};
+pub use codegen::FuncKind;
+pub use diagnostic::{ColorChoice, Diagnostic, Renderer};
+pub use doc::DocFormat;
+pub use emit::Stage;
+pub use error::{FluxError, LexErrorKind, TraceFrame};
+pub use natives::{Arity, Native, NativeFn, Natives, VmCtx};
+pub use span::Span;
+pub use vm::{Limits, Value, VM};
+
+fn parse(source: &str) -> Result<Vec<ast::Stmt>, Vec<FluxError>> {
+    let tokens = lexer::Lexer::new(source).lex().map_err(|e| vec![e])?;
+    let (program, errors) = parser::Parser::new(tokens).parse();
+    if errors.is_empty() { Ok(program) } else { Err(errors) }
+}
+
+/// Parse and statically check `source`, returning the program and the
+/// signature of each `purpose`.
+fn analyze(source: &str, natives: &Natives) -> Result<(Vec<ast::Stmt>, Vec<String>), Vec<FluxError>> {
+    let program = parse(source)?;
+    let errors = semantic::check(&program, natives);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let (signatures, errors) = types::check(&program);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok((program, signatures))
+}
+
+/// Check `source` without compiling it. On success, returns the inferred
+/// signature of every `purpose`, such as `purpose first(xs: [a]): a`.
+pub fn check(source: &str) -> Result<Vec<String>, Vec<FluxError>> {
+    analyze(source, &Natives::default()).map(|(_, signatures)| signatures)
+}
+
+/// Compile `source`. `path` is recorded in the program so runtime errors
+/// can be rendered against the file later.
+pub fn compile(path: &str, source: &str) -> Result<Program, Vec<FluxError>> {
+    compile_with(path, source, &Natives::default())
+}
+
+/// Compile `source` against `natives` instead of the built-ins; run it on a
+/// VM that has the same natives registered.
+pub fn compile_with(path: &str, source: &str, natives: &Natives) -> Result<Program, Vec<FluxError>> {
+    let (program, _) = analyze(source, natives)?;
+    let mut cg = codegen::Codegen::new();
+    cg.compile(&program);
+    Ok(cg.program(path))
+}
+
+/// A reference for `source`, titled `title`: its courses, purposes,
+/// constants and documented mutables with their signatures and doc comments.
+pub fn doc(title: &str, source: &str, format: DocFormat) -> Result<String, Vec<FluxError>> {
+    parse(source).map(|program| doc::render(title, &program, format))
+}
+
+/// `source` as text after `stage`: its tokens, its AST, or its bytecode.
+pub fn dump(source: &str, stage: Stage) -> Result<String, Vec<FluxError>> {
+    match stage {
+        Stage::Tokens => lexer::Lexer::new(source).lex().map(|tokens| emit::tokens(&tokens)).map_err(|e| vec![e]),
+        Stage::Ast => parse(source).map(|program| emit::ast(&program)),
+        Stage::Ir => compile("", source).map(|program| program.disassemble()),
+    }
+}
@@ -1,96 +1,270 @@
 // src/main.rs
-mod ast;
-mod lexer;
-mod parser;
-mod codegen;
-mod vm;
-mod error;
-
+use project_flux::{golden, repl, ColorChoice, Diagnostic, DocFormat, FluxError, Limits, Program, Renderer, Stage, VM};
 use std::env;
 use std::fs;
+use std::path::Path;
 use std::process;
+use std::time::Duration;
+
+/// Print a diagnostic that is not tied to any source file and exit.
+fn fail(diag: Diagnostic, color: bool) -> ! {
+    eprint!("{}", Renderer::new("", "", color).render(&diag));
+    process::exit(1);
+}
+
+const USAGE: &str = "[run|check|compile|doc|repl|test] [--color=auto|always|never] [--emit=tokens|ast|ir] [--show-types] \
+[--format=markdown|html] [--output=FILE] [--max-steps=N] [--timeout=DURATION] [--max-depth=N] [--max-size=N] <file.fl|file.flc|dir>";
+
+/// Parse a numeric limit flag; `unlimited` lifts the limit.
+fn parse_count(flag: &str, value: &str) -> Result<Option<usize>, Diagnostic> {
+    if value == "unlimited" {
+        return Ok(None);
+    }
+    value.replace('_', "").parse().map(Some).map_err(|_| {
+        Diagnostic::error(format!("invalid value `{}` for {}", value, flag))
+            .with_help("expected a whole number or `unlimited`")
+    })
+}
+
+/// Parse `--timeout`: `500ms`, `2s`, or a bare number of milliseconds.
+fn parse_timeout(value: &str) -> Result<Option<Duration>, Diagnostic> {
+    if value == "unlimited" {
+        return Ok(None);
+    }
+    let (digits, scale) = if let Some(ms) = value.strip_suffix("ms") {
+        (ms, 1)
+    } else if let Some(secs) = value.strip_suffix('s') {
+        (secs, 1000)
+    } else {
+        (value, 1)
+    };
+    digits.parse::<u64>().ok()
+        .and_then(|n| n.checked_mul(scale))
+        .map(|ms| Some(Duration::from_millis(ms)))
+        .ok_or_else(|| Diagnostic::error(format!("invalid value `{}` for --timeout", value))
+            .with_help("expected a duration such as `500ms`, `2s` or `unlimited`"))
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    
-    if args.len() != 2 {
-        eprintln!("Usage: {} <file.fl>", args.get(0).unwrap_or(&"flux".to_string()));
-        eprintln!("Example: cargo run -- example.fl");
-        process::exit(1);
+    let program_name = args.first().map(String::as_str).unwrap_or("flux");
+
+    let mut color = ColorChoice::Auto;
+    let mut limits = Limits::default();
+    let mut command = None;
+    let mut show_types = false;
+    let mut output = None;
+    let mut emit = None;
+    let mut format = None;
+    let mut files = vec![];
+    for arg in &args[1..] {
+        let (flag, value) = arg.split_once('=').unwrap_or((arg.as_str(), ""));
+        let parsed = match flag {
+            "--color" => ColorChoice::from_name(value).map(|c| color = c).ok_or_else(|| {
+                Diagnostic::error(format!("invalid value `{}` for --color", value))
+                    .with_help("expected one of `auto`, `always`, `never`")
+            }),
+            "--emit" => Stage::from_name(value).map(|s| emit = Some(s)).ok_or_else(|| {
+                Diagnostic::error(format!("invalid value `{}` for --emit", value))
+                    .with_help("expected one of `tokens`, `ast`, `ir`")
+            }),
+            "--format" => DocFormat::from_name(value).map(|f| format = Some(f)).ok_or_else(|| {
+                Diagnostic::error(format!("invalid value `{}` for --format", value))
+                    .with_help("expected one of `markdown`, `html`")
+            }),
+            "--show-types" if value.is_empty() => {
+                show_types = true;
+                Ok(())
+            }
+            "--output" if !value.is_empty() => {
+                output = Some(value.to_string());
+                Ok(())
+            }
+            "--max-steps" => parse_count(flag, value).map(|n| limits.max_steps = n.map(|n| n as u64)),
+            "--timeout" => parse_timeout(value).map(|t| limits.timeout = t),
+            "--max-depth" => parse_count(flag, value).map(|n| limits.max_call_depth = n),
+            "--max-size" => parse_count(flag, value).map(|n| limits.max_size = n),
+            _ if arg.starts_with("--") => Err(Diagnostic::error(format!("unknown option `{}`", arg))
+                .with_note(format!("usage: {} {}", program_name, USAGE))),
+            "run" | "check" | "compile" | "doc" | "repl" | "test" if command.is_none() && files.is_empty() => {
+                command = Some(arg.as_str());
+                Ok(())
+            }
+            _ => {
+                files.push(arg.as_str());
+                Ok(())
+            }
+        };
+        if let Err(diag) = parsed {
+            fail(diag, ColorChoice::Auto.enabled());
+        }
+    }
+    let color = color.enabled();
+    if show_types && command != Some("check") {
+        fail(Diagnostic::error("`--show-types` is only supported by `check`")
+            .with_help("try `flux check --show-types <file.fl>`"), color);
+    }
+    if emit.is_some() && command.is_some_and(|c| c != "run") {
+        fail(Diagnostic::error(format!("`--emit` can't be combined with `{}`", command.unwrap_or_default()))
+            .with_help("try `flux --emit=ir <file.fl>`"), color);
+    }
+    if output.is_some() && !matches!(command, Some("compile" | "doc")) {
+        fail(Diagnostic::error("`--output` is only supported by `compile` and `doc`")
+            .with_help("try `flux compile --output=prog.flc <file.fl>`"), color);
+    }
+    if format.is_some() && command != Some("doc") {
+        fail(Diagnostic::error("`--format` is only supported by `doc`")
+            .with_help("try `flux doc --format=html <file.fl>`"), color);
+    }
+
+    if command == Some("repl") {
+        if !files.is_empty() {
+            fail(Diagnostic::error("`repl` doesn't take a file")
+                .with_help("try `flux repl`"), color);
+        }
+        repl::start(limits, color);
+        return;
+    }
+
+    if command == Some("test") {
+        let [dir] = files[..] else {
+            fail(Diagnostic::error("`test` takes one directory")
+                .with_help("try `flux test tests/corpus`"), color);
+        };
+        let outcomes = match golden::run_dir(Path::new(dir)) {
+            Ok(outcomes) => outcomes,
+            Err(e) => fail(Diagnostic::error(format!("couldn't read `{}`: {}", dir, e)), color),
+        };
+        for outcome in &outcomes {
+            print!("{}", outcome);
+        }
+        let failed = outcomes.iter().filter(|o| !o.passed()).count();
+        println!();
+        println!("{} passed, {} failed", outcomes.len() - failed, failed);
+        if failed > 0 {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if files.len() != 1 {
+        fail(Diagnostic::error(format!("usage: {} {}", program_name, USAGE))
+            .with_help("try `cargo run -- example.fl`"), color);
     }
 
-    let path = &args[1];
+    let path = files[0];
+
+    if path.ends_with(".flc") {
+        if emit.is_some_and(|e| e != Stage::Ir) {
+            fail(Diagnostic::error(format!("`{}` is compiled bytecode; only `--emit=ir` applies to it", path)), color);
+        }
+        if command.is_some_and(|c| c != "run") {
+            fail(Diagnostic::error(format!("`{}` is compiled bytecode", path))
+                .with_help(format!("`{}` needs the `.fl` source file", command.unwrap_or_default())), color);
+        }
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => fail(Diagnostic::error(format!("couldn't read `{}`: {}", path, e)), color),
+        };
+        let program = match Program::decode(&bytes) {
+            Ok(program) => program,
+            Err(e) => {
+                let diag = Diagnostic::error(format!("couldn't load `{}`: {}", path, e));
+                fail(diag.with_help("recompile it with `flux compile <file.fl>`"), color);
+            }
+        };
+        // Spans point into the original source; render them against it if
+        // it is still around.
+        let source = fs::read_to_string(&program.source).unwrap_or_default();
+        let renderer = Renderer::new(&program.source, &source, color);
+        if emit.is_some() {
+            print!("{}", program.disassemble());
+            return;
+        }
+        run(&program, limits, &renderer);
+        return;
+    }
 
     if !path.ends_with(".fl") {
-        eprintln!("Error: Flux files must have .fl extension");
-        eprintln!("Example: cargo run -- example.fl");
-        process::exit(1);
+        fail(Diagnostic::error(format!("`{}` is not a Flux source file", path))
+            .with_note("Flux files must have the .fl extension, or .flc for compiled bytecode")
+            .with_help("try `cargo run -- example.fl`"), color);
     }
 
     let source = match fs::read_to_string(path) {
         Ok(content) => content,
-        Err(e) => {
-            eprintln!("Error reading file '{}': {}", path, e);
-            process::exit(1);
-        }
+        Err(e) => fail(Diagnostic::error(format!("couldn't read `{}`: {}", path, e)), color),
     };
+    let renderer = Renderer::new(path, &source, color);
 
-    // COMMENTED: Source code display (not Flux output)
-    // println!("=== Loading: {} ===", path);
-    // println!("{}", source);
-    // println!("================{}", "=".repeat(path.len()));
-
-    let tokens = match lexer::Lexer::new(&source).lex() {
-        Ok(tokens) => tokens,
-        Err(e) => {
-            eprintln!("Lexer Error: {:?}", e);
-            process::exit(1);
+    if let Some(stage) = emit {
+        match project_flux::dump(&source, stage) {
+            Ok(text) => print!("{}", text),
+            Err(errors) => abort(&errors, &renderer, color),
         }
-    };
+        return;
+    }
+    if command == Some("doc") {
+        // An `.html` output file implies the format.
+        let format = format.unwrap_or(match &output {
+            Some(out) if out.ends_with(".html") => DocFormat::Html,
+            _ => DocFormat::Markdown,
+        });
+        let title = Path::new(path).file_name().map_or(path.into(), |name| name.to_string_lossy());
+        let text = project_flux::doc(&title, &source, format).unwrap_or_else(|errors| abort(&errors, &renderer, color));
+        match output {
+            Some(out) => {
+                if let Err(e) = fs::write(&out, text) {
+                    fail(Diagnostic::error(format!("couldn't write `{}`: {}", out, e)), color);
+                }
+                println!("{}: documented in {}", path, out);
+            }
+            None => print!("{}", text),
+        }
+        return;
+    }
+    if command == Some("check") {
+        let signatures = project_flux::check(&source).unwrap_or_else(|errors| abort(&errors, &renderer, color));
+        if show_types {
+            for signature in &signatures {
+                println!("{}", signature);
+            }
+        }
+        println!("{}: no errors found", path);
+        return;
+    }
 
-    // COMMENTED: Token display (not Flux output)
-    // println!("=== Tokens ===");
-    // for (i, token) in tokens.iter().enumerate() {
-    //     println!("{:3}: {:?}", i, token);
-    // }
-    // println!("==============");
-
-    let program = match parser::Parser::new(tokens).parse() {
-        Ok(program) => program,
-        Err(e) => {
-            eprintln!("Parser Error: {:?}", e);
-            process::exit(1);
+    let program = project_flux::compile(path, &source).unwrap_or_else(|errors| abort(&errors, &renderer, color));
+    if command == Some("compile") {
+        let out = output.unwrap_or_else(|| format!("{}c", path));
+        if let Err(e) = fs::write(&out, program.encode()) {
+            fail(Diagnostic::error(format!("couldn't write `{}`: {}", out, e)), color);
         }
-    };
+        println!("{}: compiled to {}", path, out);
+        return;
+    }
 
-    // COMMENTED: AST display (not Flux output)
-    // println!("=== AST ===");
-    // for (i, stmt) in program.iter().enumerate() {
-    //     println!("{:3}: {:?}", i, stmt);
-    // }
-    // println!("===========");
-
-    let mut cg = codegen::Codegen::new();
-    cg.compile(&program);
-
-    // COMMENTED: IR display (not Flux output)
-    // println!("=== Generated IR ===");
-    // for (i, ir) in cg.code.iter().enumerate() {
-    //     println!("{:3}: {:?}", i, ir);
-    // }
-    // println!("Functions: {:?}", cg.functions);
-    // println!("===================");
-
-    // COMMENTED: Execution header (not Flux output)
-    // println!("=== Execution ===");
+    run(&program, limits, &renderer);
+}
+
+/// Print compile errors and exit.
+fn abort(errors: &[FluxError], renderer: &Renderer, color: bool) -> ! {
+    for e in errors {
+        eprint!("{}", renderer.render(&Diagnostic::from(e)));
+    }
+    fail(Diagnostic::error(format!("aborting due to {} previous error{}", errors.len(), if errors.len() == 1 { "" } else { "s" })), color);
+}
+
+fn run(program: &Program, limits: Limits, renderer: &Renderer) {
 	println!(" ");
 	println!(" ");
 
-    let mut vm = vm::VM::new();
-    vm.run(&cg.code, &cg.functions); // ONLY this produces actual Flux program output
-    // COMMENTED: Execution footer (not Flux output)
-    // println!("\n=================");
+    let mut vm = VM::with_limits(limits);
+    // ONLY this produces actual Flux program output
+    if let Err(e) = vm.run(program) {
+        eprint!("{}", renderer.render(&Diagnostic::from(&e)));
+        process::exit(e.exit_code());
+    }
 	println!(" ");
 	println!(" ");
-
-}
\ No newline at end of file
+}
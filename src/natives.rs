@@ -0,0 +1,122 @@
+// src/natives.rs
+//
+// Functions written in Rust and callable from Flux. The built-ins are
+// registered here exactly the way an embedder registers its own; the
+// semantic checker reads arities from the registry and the VM links calls
+// against it by name before a program runs.
+use crate::error::FluxError;
+use crate::vm::Value;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+/// How many arguments a native function takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Fixed(usize),
+    Variadic,
+}
+
+pub type NativeFn = dyn Fn(&mut VmCtx, &[Value]) -> Result<Value, FluxError> + Send + Sync;
+
+#[derive(Clone)]
+pub struct Native {
+    pub arity: Arity,
+    pub(crate) func: Arc<NativeFn>,
+}
+
+/// Native functions by name.
+#[derive(Clone)]
+pub struct Natives {
+    entries: HashMap<String, Native>,
+}
+
+impl Default for Natives {
+    /// The built-ins: `report` and `getInput`.
+    fn default() -> Self {
+        let mut natives = Natives::empty();
+        natives.register("report", Arity::Variadic, report);
+        natives.register("getInput", Arity::Variadic, get_input);
+        natives
+    }
+}
+
+impl Natives {
+    /// A registry without even the built-ins.
+    pub fn empty() -> Self {
+        Natives { entries: HashMap::new() }
+    }
+
+    /// Add `name`, replacing any native already registered under it.
+    pub fn register<F>(&mut self, name: impl Into<String>, arity: Arity, func: F) -> &mut Self
+    where
+        F: Fn(&mut VmCtx, &[Value]) -> Result<Value, FluxError> + Send + Sync + 'static,
+    {
+        self.entries.insert(name.into(), Native { arity, func: Arc::new(func) });
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Native> {
+        self.entries.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Native)> {
+        self.entries.iter().map(|(name, native)| (name.as_str(), native))
+    }
+}
+
+/// Where `getInput` reads from.
+pub(crate) enum Input {
+    /// The process's stdin, locked only for each read so a host such as the
+    /// REPL can share it.
+    Stdin,
+    Reader(Box<dyn BufRead + Send>),
+}
+
+/// What a native function can reach while it runs.
+pub struct VmCtx<'a> {
+    pub(crate) input: &'a mut Input,
+    pub(crate) output: &'a mut dyn Write,
+    pub(crate) error: &'a mut dyn Write,
+}
+
+impl VmCtx<'_> {
+    pub fn write(&mut self, text: &str) -> Result<(), FluxError> {
+        self.output.write_all(text.as_bytes())
+            .map_err(|e| FluxError::runtime(format!("couldn't write output: {}", e)))
+    }
+
+    /// Write to the error stream, which is not buffered by the VM.
+    pub fn write_error(&mut self, text: &str) -> Result<(), FluxError> {
+        self.error.write_all(text.as_bytes())
+            .map_err(|e| FluxError::runtime(format!("couldn't write to the error stream: {}", e)))
+    }
+
+    /// Flush output, then read one line of input without its line ending;
+    /// empty at end of input.
+    pub fn read_line(&mut self) -> Result<String, FluxError> {
+        self.output.flush().map_err(|e| FluxError::runtime(format!("couldn't write output: {}", e)))?;
+        let mut line = String::new();
+        let read = match self.input {
+            Input::Stdin => io::stdin().read_line(&mut line),
+            Input::Reader(reader) => reader.read_line(&mut line),
+        };
+        read.map_err(|e| FluxError::runtime(format!("couldn't read input: {}", e)))?;
+        Ok(line.trim_end_matches(['\n', '\r']).to_string())
+    }
+}
+
+/// Print each argument followed by a space, then a newline.
+fn report(ctx: &mut VmCtx, args: &[Value]) -> Result<Value, FluxError> {
+    for arg in args {
+        ctx.write(&format!("{} ", arg))?;
+    }
+    ctx.write("\n")?;
+    Ok(Value::Null)
+}
+
+/// Prompt for and read one line. Arguments are ignored.
+fn get_input(ctx: &mut VmCtx, _args: &[Value]) -> Result<Value, FluxError> {
+    ctx.write("Input: ")?;
+    Ok(Value::Str(ctx.read_line()?.trim().to_string()))
+}
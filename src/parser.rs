@@ -1,32 +1,71 @@
 // src/parser.rs
 use crate::ast::*;
 use crate::error::FluxError;
+use crate::span::{Span, Spanned};
 
 type PResult<T> = Result<T, FluxError>;
 
+/// Drop doc comments that don't come right before a declaration: those are
+/// ordinary comments, such as a `///` banner between sections.
+fn attached_docs(tokens: Vec<Spanned<crate::lexer::Token>>) -> Vec<Spanned<crate::lexer::Token>> {
+    let mut kept = Vec::with_capacity(tokens.len());
+    let mut docs = vec![];
+    for token in tokens {
+        match token.node {
+            crate::lexer::Token::Doc(_) => docs.push(token),
+            crate::lexer::Token::Course | crate::lexer::Token::Purpose |
+            crate::lexer::Token::Constant | crate::lexer::Token::Mutable => {
+                kept.append(&mut docs);
+                kept.push(token);
+            }
+            _ => {
+                docs.clear();
+                kept.push(token);
+            }
+        }
+    }
+    kept
+}
+
 pub struct Parser {
-    tokens: Vec<crate::lexer::Token>,
+    tokens: Vec<Spanned<crate::lexer::Token>>,
     pos: usize,
+    errors: Vec<FluxError>,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<crate::lexer::Token>) -> Self { 
-        Self { tokens, pos: 0 } 
+    pub fn new(tokens: Vec<Spanned<crate::lexer::Token>>) -> Self { 
+        Self { tokens: attached_docs(tokens), pos: 0, errors: vec![] }
     }
 
     fn cur(&self) -> &crate::lexer::Token { 
         if self.pos < self.tokens.len() {
-            &self.tokens[self.pos]
+            &self.tokens[self.pos].node
         } else {
-            &crate::lexer::Token::EOF
+            &crate::lexer::Token::Eof
         }
     }
 
+    fn cur_span(&self) -> Span {
+        self.tokens.get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|t| t.span)
+            .unwrap_or_default()
+    }
+
+    /// Span of the most recently consumed token; used to close node spans.
+    fn prev_span(&self) -> Span {
+        if self.pos == 0 {
+            return self.cur_span();
+        }
+        self.tokens[self.pos - 1].span
+    }
+
     fn advance(&mut self) -> &crate::lexer::Token { 
         if self.pos < self.tokens.len() {
             self.pos += 1; 
         }
-        &self.tokens[self.pos - 1] 
+        &self.tokens[self.pos - 1].node
     }
 
     fn eat(&mut self, expected: crate::lexer::Token) -> PResult<()> {
@@ -34,20 +73,86 @@ impl Parser {
             self.advance();
             Ok(())
         } else {
-            Err(FluxError::Parse(format!("Expected {:?}, found {:?} at position {}", expected, self.cur(), self.pos)))
+            let message = format!("Expected {}, found {}", expected, self.cur());
+            if expected == crate::lexer::Token::Semicolon && self.pos > 0 {
+                // Point at the end of the statement rather than at whatever follows it.
+                Err(FluxError::parse(message, self.prev_span())
+                    .with_help("add `;` at the end of this statement"))
+            } else {
+                Err(FluxError::parse(message, self.cur_span()))
+            }
         }
     }
 
-    pub fn parse(&mut self) -> PResult<Vec<Stmt>> {
+    /// Parse the whole program, recovering from syntax errors so that every
+    /// one of them is reported. The returned AST omits statements that failed.
+    pub fn parse(&mut self) -> (Vec<Stmt>, Vec<FluxError>) {
         let mut stmts = vec![];
-        while !matches!(self.cur(), crate::lexer::Token::EOF) {
-            stmts.push(self.stmt()?);
+        while !matches!(self.cur(), crate::lexer::Token::Eof) {
+            if matches!(self.cur(), crate::lexer::Token::RBrace) {
+                self.errors.push(FluxError::parse("Unmatched `}`", self.cur_span()));
+                self.advance();
+                continue;
+            }
+            if let Some(stmt) = self.recovering_stmt() {
+                stmts.push(stmt);
+            }
+        }
+        (stmts, std::mem::take(&mut self.errors))
+    }
+
+    fn recovering_stmt(&mut self) -> Option<Stmt> {
+        let start = self.pos;
+        match self.stmt() {
+            Ok(stmt) => Some(stmt),
+            Err(e) => {
+                self.errors.push(e);
+                if self.pos == start {
+                    self.advance();
+                }
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    /// Panic-mode recovery: skip tokens until just past a `;` or a braced
+    /// block, or until a `}` or a keyword that can only begin a statement.
+    fn synchronize(&mut self) {
+        loop {
+            if self.pos > 0 && matches!(self.tokens[self.pos - 1].node, crate::lexer::Token::Semicolon) {
+                return;
+            }
+            match self.cur() {
+                crate::lexer::Token::Eof | crate::lexer::Token::RBrace |
+                crate::lexer::Token::When | crate::lexer::Token::Persist |
+                crate::lexer::Token::Iterate | crate::lexer::Token::Course |
+                crate::lexer::Token::Purpose | crate::lexer::Token::Constant |
+                crate::lexer::Token::Mutable | crate::lexer::Token::Assign |
+                crate::lexer::Token::Yield | crate::lexer::Token::Doc(_) => return,
+                crate::lexer::Token::LBrace => {
+                    // A block whose header was malformed: skip it whole so its
+                    // body doesn't surface as a cascade of stray statements.
+                    let mut depth = 0;
+                    loop {
+                        match self.cur() {
+                            crate::lexer::Token::LBrace => depth += 1,
+                            crate::lexer::Token::RBrace => depth -= 1,
+                            crate::lexer::Token::Eof => return,
+                            _ => {}
+                        }
+                        self.advance();
+                        if depth == 0 { return; }
+                    }
+                }
+                _ => { self.advance(); }
+            }
         }
-        Ok(stmts)
     }
 
     fn stmt(&mut self) -> PResult<Stmt> {
         match self.cur() {
+            crate::lexer::Token::Doc(_) => self.documented(),
             crate::lexer::Token::Constant => self.const_decl(),
             crate::lexer::Token::Mutable => self.mutable_decl(),
             crate::lexer::Token::Assign => self.assign(),
@@ -58,87 +163,110 @@ impl Parser {
             crate::lexer::Token::When => self.when(),
             crate::lexer::Token::Iterate => self.iterate_loop(),
             crate::lexer::Token::LBrace => {
+                let start = self.cur_span();
                 let block = self.block()?;
-                Ok(Stmt::Block(block))
+                Ok(Stmt::new(StmtKind::Block(block), start.to(self.prev_span())))
             }
             _ => {
                 if self.is_assignment_target() {
                     self.assignment_stmt()
                 } else {
+                    let start = self.cur_span();
                     let expr = self.expr()?;
                     self.eat(crate::lexer::Token::Semicolon)?;
-                    Ok(Stmt::Expr(expr))
+                    Ok(Stmt::new(StmtKind::Expr(expr), start.to(self.prev_span())))
                 }
             }
         }
     }
 
+    /// Doc comments and the declaration they document.
+    fn documented(&mut self) -> PResult<Stmt> {
+        let mut lines = vec![];
+        while let crate::lexer::Token::Doc(line) = self.cur() {
+            lines.push(line.clone());
+            self.advance();
+        }
+        let mut stmt = self.stmt()?;
+        stmt.doc = Some(lines.join("\n"));
+        Ok(stmt)
+    }
+
     fn is_assignment_target(&self) -> bool {
+        let token = |pos: usize| self.tokens.get(pos).map(|t| &t.node);
         let mut pos = self.pos;
         
-        if !matches!(self.tokens.get(pos), Some(crate::lexer::Token::Ident(_))) {
+        if !matches!(token(pos), Some(crate::lexer::Token::Ident(_))) {
             return false;
         }
         pos += 1;
         
-        while matches!(self.tokens.get(pos), Some(crate::lexer::Token::LBracket)) {
+        while matches!(token(pos), Some(crate::lexer::Token::LBracket)) {
             pos += 1;
-            if !matches!(self.tokens.get(pos), Some(crate::lexer::Token::Int(_) | crate::lexer::Token::Ident(_))) {
+            if !matches!(token(pos), Some(crate::lexer::Token::Int(_) | crate::lexer::Token::Ident(_))) {
                 return false;
             }
             pos += 1;
-            if !matches!(self.tokens.get(pos), Some(crate::lexer::Token::RBracket)) {
+            if !matches!(token(pos), Some(crate::lexer::Token::RBracket)) {
                 return false;
             }
             pos += 1;
         }
         
-        matches!(self.tokens.get(pos), Some(crate::lexer::Token::Eq))
+        matches!(token(pos), Some(crate::lexer::Token::Eq))
     }
 
     fn assignment_stmt(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         let target = self.expr()?;
         self.eat(crate::lexer::Token::Eq)?;
         let value = self.expr()?;
         self.eat(crate::lexer::Token::Semicolon)?;
+        let span = start.to(self.prev_span());
         
-        match target {
-            Expr::Ident(name) => Ok(Stmt::Assign { name, value }),
-            Expr::Index { target, index, value: _ } => {
-                if let Expr::Ident(var_name) = *target {
-                    let var_name_clone = var_name.clone();
-                    Ok(Stmt::Assign { 
-                        name: var_name, 
-                        value: Expr::Index {
-                            target: Box::new(Expr::Ident(var_name_clone)),
+        match target.kind {
+            ExprKind::Ident(name) => Ok(Stmt::new(StmtKind::Assign { name, value }, span)),
+            ExprKind::Index { target, index, value: _ } => {
+                if let ExprKind::Ident(var_name) = &target.kind {
+                    let value_span = target.span.to(value.span);
+                    Ok(Stmt::new(StmtKind::Assign { 
+                        name: var_name.clone(), 
+                        value: Expr::new(ExprKind::Index {
+                            target,
                             index,
                             value: Some(Box::new(value)),
-                        }
-                    })
+                        }, value_span)
+                    }, span))
                 } else {
-                    Err(FluxError::Parse("Invalid assignment target".to_string()))
+                    Err(FluxError::parse("Invalid assignment target", target.span)
+                        .with_help("only variables and list elements can be assigned"))
                 }
             }
-            _ => Err(FluxError::Parse("Invalid assignment target".to_string()))
+            _ => Err(FluxError::parse("Invalid assignment target", target.span)
+                .with_help("only variables and list elements can be assigned"))
         }
     }
 
     fn const_decl(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::Constant)?; 
         let name = self.ident()?;
-        let value = if matches!(self.cur(), crate::lexer::Token::Eq) {
-            self.advance();
-            self.expr()?
-        } else {
-            Expr::Int(0)
-        };
+        let ty = self.annotation(false)?;
+        if !matches!(self.cur(), crate::lexer::Token::Eq) {
+            return Err(FluxError::parse(format!("Constant `{}` needs a value", name), start.to(self.prev_span()))
+                .with_help(format!("write `constant {} = <value>;`, or use `mutable` for a variable set later", name)));
+        }
+        self.advance();
+        let value = self.expr()?;
         self.eat(crate::lexer::Token::Semicolon)?;
-        Ok(Stmt::Const { name, value })
+        Ok(Stmt::new(StmtKind::Const { name, ty, value }, start.to(self.prev_span())))
     }
 
     fn mutable_decl(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::Mutable)?; 
         let name = self.ident()?;
+        let ty = self.annotation(false)?;
         let init = if matches!(self.cur(), crate::lexer::Token::Eq) { 
             self.advance(); 
             Some(self.expr()?) 
@@ -146,19 +274,21 @@ impl Parser {
             None 
         };
         self.eat(crate::lexer::Token::Semicolon)?;
-        Ok(Stmt::Mutable { name, init })
+        Ok(Stmt::new(StmtKind::Mutable { name, ty, init }, start.to(self.prev_span())))
     }
 
     fn assign(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::Assign)?; 
         let name = self.ident()?; 
         self.eat(crate::lexer::Token::Eq)?; 
         let value = self.expr()?; 
         self.eat(crate::lexer::Token::Semicolon)?;
-        Ok(Stmt::Assign { name, value })
+        Ok(Stmt::new(StmtKind::Assign { name, value }, start.to(self.prev_span())))
     }
 
     fn yield_stmt(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::Yield)?;
         let val = if !matches!(self.cur(), crate::lexer::Token::Semicolon) { 
             Some(self.expr()?) 
@@ -166,37 +296,43 @@ impl Parser {
             None 
         };
         self.eat(crate::lexer::Token::Semicolon)?;
-        Ok(Stmt::Return(val))
+        Ok(Stmt::new(StmtKind::Return(val), start.to(self.prev_span())))
     }
 
     fn course(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::Course)?; 
         let name = self.ident()?; 
         self.eat(crate::lexer::Token::LParen)?; 
         let params = self.params()?; 
         self.eat(crate::lexer::Token::RParen)?; 
+        let ret = self.annotation(true)?;
         let body = self.block()?;
-        Ok(Stmt::Course { name, params, body })
+        Ok(Stmt::new(StmtKind::Course { name, params, ret, body }, start.to(self.prev_span())))
     }
 
     fn purpose(&mut self) -> PResult<Stmt> {
+		let start = self.cur_span();
 		self.eat(crate::lexer::Token::Purpose)?; 
 		let name = self.ident()?; 
 		self.eat(crate::lexer::Token::LParen)?; 
 		let params = self.params()?; 
 		self.eat(crate::lexer::Token::RParen)?; 
+		let ret = self.annotation(true)?;
 		let body = self.block()?;
-		Ok(Stmt::Purpose { name, params, body })  // CHANGED: Stmt::Purpose
+		Ok(Stmt::new(StmtKind::Purpose { name, params, ret, body }, start.to(self.prev_span())))  // CHANGED: Stmt::Purpose
 	}
 
     fn persist(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::Persist)?; 
         let cond = self.expr()?; 
         let body = self.block()?;
-        Ok(Stmt::Persist { cond, body })
+        Ok(Stmt::new(StmtKind::Persist { cond, body }, start.to(self.prev_span())))
     }
 
     fn when(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::When)?; 
         let cond = self.expr()?; 
         self.eat(crate::lexer::Token::Then)?; 
@@ -214,49 +350,98 @@ impl Parser {
         } else { 
             vec![] 
         };
-        Ok(Stmt::When { cond, then, elifs, otherwise })
+        Ok(Stmt::new(StmtKind::When { cond, then, elifs, otherwise }, start.to(self.prev_span())))
     }
 
     fn iterate_loop(&mut self) -> PResult<Stmt> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::Iterate)?;
-        let var = self.ident()?;
+        let mut var = self.ident()?;
+        let mut index = None;
+        if matches!(self.cur(), crate::lexer::Token::Comma) {
+            self.advance();
+            index = Some(var);
+            var = self.ident()?;
+        }
         self.eat(crate::lexer::Token::Across)?;
-        let iterable = self.expr()?;  // This can be a range (1 to 10) or list
+        let iterable = self.expr()?;  // This can be a range (1 to 10), list or string
         let body = self.block()?;
-        Ok(Stmt::Iterate { var, iterable, body })
+        Ok(Stmt::new(StmtKind::Iterate { var, index, iterable, body }, start.to(self.prev_span())))
     }
 
     fn block(&mut self) -> PResult<Vec<Stmt>> {
         self.eat(crate::lexer::Token::LBrace)?; 
         let mut stmts = vec![]; 
-        while !matches!(self.cur(), crate::lexer::Token::RBrace) { 
-            stmts.push(self.stmt()?); 
+        while !matches!(self.cur(), crate::lexer::Token::RBrace | crate::lexer::Token::Eof) { 
+            if let Some(stmt) = self.recovering_stmt() {
+                stmts.push(stmt);
+            }
         } 
         self.eat(crate::lexer::Token::RBrace)?; 
         Ok(stmts)
     }
 
-    fn params(&mut self) -> PResult<Vec<String>> {
+    fn params(&mut self) -> PResult<Vec<Param>> {
         let mut p = vec![];
         if matches!(self.cur(), crate::lexer::Token::RParen) { 
             return Ok(p); 
         }
         loop {
             let name = self.ident()?; 
-            p.push(name);
+            let ty = self.annotation(false)?;
+            p.push(Param { name, ty });
             if !matches!(self.cur(), crate::lexer::Token::Comma) { break; } 
             self.advance();
         }
         Ok(p)
     }
 
+    /// An optional `: type` annotation. `void` is only accepted where a
+    /// return type is expected.
+    fn annotation(&mut self, allow_void: bool) -> PResult<Option<TypeAnn>> {
+        if !matches!(self.cur(), crate::lexer::Token::Colon) {
+            return Ok(None);
+        }
+        self.advance();
+        let ty = self.type_name()?;
+        if ty.node == TypeName::Void && !allow_void {
+            return Err(FluxError::parse("`void` is only allowed as a return type", ty.span)
+                .with_help("drop the annotation, or use `number`, `float`, `string` or `boolean`"));
+        }
+        Ok(Some(ty))
+    }
+
+    fn type_name(&mut self) -> PResult<TypeAnn> {
+        let start = self.cur_span();
+        let name = match self.cur() {
+            crate::lexer::Token::NumberType => TypeName::Number,
+            crate::lexer::Token::FloatType => TypeName::Float,
+            crate::lexer::Token::StringType => TypeName::Str,
+            crate::lexer::Token::BooleanType => TypeName::Boolean,
+            crate::lexer::Token::Void => TypeName::Void,
+            crate::lexer::Token::LBracket => {
+                self.advance();
+                let element = self.type_name()?;
+                if element.node == TypeName::Void {
+                    return Err(FluxError::parse("`void` is only allowed as a return type", element.span));
+                }
+                self.eat(crate::lexer::Token::RBracket)?;
+                return Ok(Spanned::new(TypeName::List(Box::new(element.node)), start.to(self.prev_span())));
+            }
+            _ => return Err(FluxError::parse(format!("Expected a type, found {}", self.cur()), self.cur_span())
+                .with_help("types are `number`, `float`, `string`, `boolean`, `[element]` and `void`")),
+        };
+        self.advance();
+        Ok(Spanned::new(name, start))
+    }
+
     fn ident(&mut self) -> PResult<String> {
         if let crate::lexer::Token::Ident(s) = self.cur() { 
             let n = s.clone(); 
             self.advance(); 
             Ok(n) 
         } else { 
-            Err(FluxError::Parse(format!("Expected identifier, found {:?}", self.cur())))
+            Err(FluxError::parse(format!("Expected identifier, found {}", self.cur()), self.cur_span()))
         }
     }
 
@@ -271,11 +456,12 @@ impl Parser {
             if l < min { break; }
             let op = self.advance().clone();
             let right = self.prec(r)?;
-            left = Expr::Binary { 
+            let span = left.span.to(right.span);
+            left = Expr::new(ExprKind::Binary { 
                 left: Box::new(left), 
                 op, 
                 right: Box::new(right) 
-            };
+            }, span);
         }
         Ok(left)
     }
@@ -287,42 +473,63 @@ impl Parser {
             self.advance();
             let index = self.expr()?;
             self.eat(crate::lexer::Token::RBracket)?;
-            expr = Expr::Index {
+            let span = expr.span.to(self.prev_span());
+            expr = Expr::new(ExprKind::Index {
                 target: Box::new(expr),
                 index: Box::new(index),
                 value: None,
-            };
+            }, span);
         }
         
         Ok(expr)
     }
 
     fn atom(&mut self) -> PResult<Expr> {
-        match self.cur() {
+        let start = self.cur_span();
+        let kind = match self.cur() {
             crate::lexer::Token::Int(i) => { 
-                let v = *i; 
+                let v = i64::try_from(*i)
+                    .map_err(|_| crate::lexer::too_large(&i.to_string(), start))?;
                 self.advance(); 
-                Ok(Expr::Int(v)) 
+                ExprKind::Int(v)
             }
             crate::lexer::Token::Float(f) => { 
                 let v = *f; 
                 self.advance(); 
-                Ok(Expr::Float(v)) 
+                ExprKind::Float(v)
             }
             crate::lexer::Token::Str(s) => { 
                 let v = s.clone(); 
                 self.advance(); 
-                Ok(Expr::Str(v)) 
+                ExprKind::Str(v)
+            }
+            crate::lexer::Token::Interp(parts) => {
+                let parts = parts.clone();
+                self.advance();
+                let mut exprs = vec![];
+                for part in parts {
+                    match part {
+                        crate::lexer::StrPart::Text(text) => exprs.push(Expr::new(ExprKind::Str(text), start)),
+                        crate::lexer::StrPart::Expr(tokens) => {
+                            let mut inner = Parser::new(tokens);
+                            exprs.push(inner.expr()?);
+                            if !matches!(inner.cur(), crate::lexer::Token::Eof) {
+                                return Err(FluxError::parse(format!("Expected `}}` to end the interpolation, found {}", inner.cur()), inner.cur_span()));
+                            }
+                        }
+                    }
+                }
+                ExprKind::Interp(exprs)
             }
             crate::lexer::Token::True => { 
                 self.advance(); 
-                Ok(Expr::Bool(true)) 
+                ExprKind::Bool(true)
             }
             crate::lexer::Token::False => { 
                 self.advance(); 
-                Ok(Expr::Bool(false)) 
+                ExprKind::Bool(false)
             }
-            crate::lexer::Token::LBracket => self.list(),
+            crate::lexer::Token::LBracket => return self.list(),
             crate::lexer::Token::Ident(name) => {
                 let n = name.clone(); 
                 self.advance();
@@ -337,38 +544,51 @@ impl Parser {
                         }
                     }
                     self.eat(crate::lexer::Token::RParen)?;
-                    Ok(Expr::Call { callee: n, args })
+                    ExprKind::Call { callee: n, args }
                 } else {
-                    Ok(Expr::Ident(n))
+                    ExprKind::Ident(n)
                 }
             }
             crate::lexer::Token::LParen => { 
                 self.eat(crate::lexer::Token::LParen)?; 
                 let e = self.expr()?; 
                 self.eat(crate::lexer::Token::RParen)?; 
-                Ok(e) 
+                return Ok(Expr::new(e.kind, start.to(self.prev_span())));
             }
             crate::lexer::Token::Minus => { 
                 self.advance(); 
+                // `-` right before an integer literal that nothing else binds
+                // to is folded into a negative literal. That's the only way to
+                // write -9223372036854775808, whose magnitude doesn't fit.
+                if let crate::lexer::Token::Int(i) = self.cur() {
+                    let next = self.tokens.get(self.pos + 1).map(|t| &t.node);
+                    if !next.is_some_and(|t| matches!(t, crate::lexer::Token::LBracket) || self.bp(t).is_some_and(|(l, _)| l >= 9)) {
+                        let v = (*i as i64).wrapping_neg();
+                        self.advance();
+                        return Ok(Expr::new(ExprKind::Int(v), start.to(self.prev_span())));
+                    }
+                }
                 let e = self.prec(9)?; 
-                Ok(Expr::Unary { 
+                ExprKind::Unary { 
                     op: crate::lexer::Token::Minus, 
                     expr: Box::new(e) 
-                }) 
+                }
             }
             crate::lexer::Token::Not => { 
                 self.advance(); 
                 let e = self.prec(9)?; 
-                Ok(Expr::Unary { 
+                ExprKind::Unary { 
                     op: crate::lexer::Token::Not, 
                     expr: Box::new(e) 
-                }) 
+                }
             }
-            _ => Err(FluxError::Parse(format!("Unexpected token in expression: {:?}", self.cur()))),
-        }
+            _ => return Err(FluxError::parse(format!("Unexpected token in expression: {}", self.cur()), self.cur_span())),
+        };
+        Ok(Expr::new(kind, start.to(self.prev_span())))
     }
 
     fn list(&mut self) -> PResult<Expr> {
+        let start = self.cur_span();
         self.eat(crate::lexer::Token::LBracket)?;
         let mut elements = vec![];
         if !matches!(self.cur(), crate::lexer::Token::RBracket) {
@@ -379,7 +599,7 @@ impl Parser {
             }
         }
         self.eat(crate::lexer::Token::RBracket)?;
-        Ok(Expr::List(elements))
+        Ok(Expr::new(ExprKind::List(elements), start.to(self.prev_span())))
     }
 
     fn bp(&self, t: &crate::lexer::Token) -> Option<(u8, u8)> {
@@ -397,4 +617,4 @@ impl Parser {
             _ => return None,
         })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,256 @@
+// src/repl.rs
+//
+// `flux repl`: an interactive session. Every entry is checked against the
+// entries before it, compiled onto the end of one growing program and run
+// on a VM whose globals, courses and purposes outlive the entry.
+use crate::ast::{Stmt, StmtKind};
+use crate::codegen::Codegen;
+use crate::diagnostic::{Diagnostic, Renderer};
+use crate::emit;
+use crate::error::LexErrorKind;
+use crate::lexer::{Lexer, Token};
+use crate::parser::Parser;
+use crate::semantic;
+use crate::types;
+use crate::vm::{Limits, Value, VM};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+const HELP: &str = "\
+:ast      toggle printing the AST of each entry
+:ir       toggle printing the bytecode of each entry
+:reset    forget every definition and variable
+:history  show the last 20 history lines
+:help     show this message
+:quit     leave the REPL (or press Ctrl-D)";
+
+/// Entries kept in the history file.
+const HISTORY_SIZE: usize = 1000;
+
+/// Input lines, mirrored to `$FLUX_HISTORY` or `~/.flux_history`.
+struct History {
+    path: Option<PathBuf>,
+    lines: Vec<String>,
+}
+
+impl History {
+    fn load() -> Self {
+        let path = env::var_os("FLUX_HISTORY").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".flux_history")));
+        let mut lines: Vec<String> = path.as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|s| s.lines().map(String::from).collect())
+            .unwrap_or_default();
+        if lines.len() > HISTORY_SIZE {
+            lines.drain(..lines.len() - HISTORY_SIZE);
+            if let Some(p) = &path {
+                let _ = fs::write(p, lines.join("\n") + "\n");
+            }
+        }
+        History { path, lines }
+    }
+
+    fn add(&mut self, line: &str) {
+        self.lines.push(line.to_string());
+        // History is a convenience; an unwritable file shouldn't end the session.
+        if let Some(p) = &self.path {
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(p) {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+}
+
+struct Session {
+    /// Every accepted entry, in order, less any definitions replaced since.
+    /// Each entry is checked as the tail of this text so it can use what
+    /// earlier entries defined.
+    source: String,
+    codegen: Codegen,
+    vm: VM,
+    limits: Limits,
+    color: bool,
+    show_ast: bool,
+    show_ir: bool,
+}
+
+impl Session {
+    fn new(limits: Limits, color: bool) -> Self {
+        Session {
+            source: String::new(),
+            codegen: Codegen::new(),
+            vm: VM::with_limits(limits.clone()),
+            limits,
+            color,
+            show_ast: false,
+            show_ir: false,
+        }
+    }
+
+    fn eval(&mut self, entry: &str) {
+        let mut source = format!("{}{}", self.source, entry);
+        let tokens = match Lexer::new(&source).lex() {
+            Ok(tokens) => tokens,
+            Err(e) => return eprint!("{}", Renderer::new("<repl>", &source, self.color).render(&Diagnostic::from(&e))),
+        };
+        let (mut program, mut errors) = Parser::new(tokens).parse();
+        program = redefine(program, &mut source, self.source.len());
+        let renderer = Renderer::new("<repl>", &source, self.color);
+        if errors.is_empty() {
+            errors = semantic::check(&program, &self.vm.natives);
+        }
+        if errors.is_empty() {
+            errors = types::check(&program).1;
+        }
+        if !errors.is_empty() {
+            for e in &errors {
+                eprint!("{}", renderer.render(&Diagnostic::from(e)));
+            }
+            return;
+        }
+
+        // Earlier entries are already compiled; only this one's statements are new.
+        let at = program.partition_point(|s| s.span.start < self.source.len());
+        let mut stmts = program.split_off(at);
+        if self.show_ast {
+            print!("{}", emit::ast(&stmts));
+        }
+        // A trailing expression yields its value so it can be echoed.
+        let echo = match stmts.last_mut() {
+            Some(Stmt { kind: kind @ StmtKind::Expr(_), .. }) => {
+                if let StmtKind::Expr(e) = std::mem::replace(kind, StmtKind::Block(vec![])) {
+                    *kind = StmtKind::Return(Some(e));
+                }
+                true
+            }
+            _ => false,
+        };
+
+        let start = self.codegen.code.len();
+        self.codegen.compile(&stmts);
+        let program = self.codegen.program("<repl>");
+        if self.show_ir {
+            print!("{}", emit::ir_from(&program, start));
+        }
+        // A runtime error still leaves behind what ran before it, so the
+        // entry is kept either way.
+        self.source = source.clone();
+        match self.vm.run_at(&program, start) {
+            Ok(Value::Null) => {}
+            Ok(value) if echo => println!("{}", value),
+            Ok(_) => {}
+            Err(e) => eprint!("{}", renderer.render(&Diagnostic::from(&e))),
+        }
+    }
+}
+
+/// Drop the earlier definition of any course or purpose the entry at
+/// `entry_start` defines again. Its text is blanked out of `source` too, so
+/// later entries don't see it, while every offset and line stays put.
+fn redefine(program: Vec<Stmt>, source: &mut String, entry_start: usize) -> Vec<Stmt> {
+    let name = |stmt: &Stmt| match &stmt.kind {
+        StmtKind::Course { name, .. } | StmtKind::Purpose { name, .. } => Some(name.clone()),
+        _ => None,
+    };
+    let new: Vec<String> = program.iter().filter(|s| s.span.start >= entry_start).filter_map(name).collect();
+    program.into_iter().filter(|stmt| {
+        let replaced = stmt.span.start < entry_start && name(stmt).is_some_and(|n| new.contains(&n));
+        if replaced {
+            let blank: String = source[stmt.span.start..stmt.span.end].chars()
+                .map(|c| if c == '\n' { "\n".to_string() } else { " ".repeat(c.len_utf8()) })
+                .collect();
+            source.replace_range(stmt.span.start..stmt.span.end, &blank);
+        }
+        !replaced
+    }).collect()
+}
+
+/// Whether `entry` needs more lines: an unclosed bracket, string or block
+/// comment, or a last token that can't end a statement.
+fn incomplete(entry: &str) -> bool {
+    let tokens = match Lexer::new(entry).lex() {
+        Ok(tokens) => tokens,
+        Err(e) => return e.lex_kind() == Some(LexErrorKind::Unterminated),
+    };
+    let mut depth = 0;
+    for t in &tokens {
+        match t.node {
+            Token::LBrace | Token::LParen | Token::LBracket => depth += 1,
+            Token::RBrace | Token::RParen | Token::RBracket => depth -= 1,
+            _ => {}
+        }
+    }
+    let last = tokens.iter().rev().find(|t| !matches!(t.node, Token::Eof));
+    depth > 0 || last.is_some_and(|t| !matches!(t.node, Token::Semicolon | Token::RBrace))
+}
+
+pub fn start(limits: Limits, color: bool) {
+    let mut session = Session::new(limits, color);
+    let mut history = History::load();
+    let mut entry = String::new();
+
+    println!("Flux REPL. Type :help for commands, Ctrl-D to exit.");
+    loop {
+        print!("{}", if entry.is_empty() { "flux> " } else { "  ... " });
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        // Stdin isn't held between lines; `getInput` reads from it too.
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                println!();
+                return;
+            }
+            Ok(_) => {}
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if entry.is_empty() {
+            let command = line.trim();
+            if command.is_empty() {
+                continue;
+            }
+            if command.starts_with(':') {
+                history.add(command);
+                match command {
+                    ":ast" => {
+                        session.show_ast = !session.show_ast;
+                        println!("AST printing {}", if session.show_ast { "on" } else { "off" });
+                    }
+                    ":ir" => {
+                        session.show_ir = !session.show_ir;
+                        println!("bytecode printing {}", if session.show_ir { "on" } else { "off" });
+                    }
+                    ":reset" => {
+                        let (show_ast, show_ir) = (session.show_ast, session.show_ir);
+                        session = Session { show_ast, show_ir, ..Session::new(session.limits, color) };
+                        println!("session reset");
+                    }
+                    ":history" => {
+                        let from = history.lines.len().saturating_sub(20);
+                        for (i, line) in history.lines.iter().enumerate().skip(from) {
+                            println!("{:>5}  {}", i + 1, line);
+                        }
+                    }
+                    ":help" => println!("{}", HELP),
+                    ":quit" | ":q" => return,
+                    _ => eprint!("{}", Renderer::new("", "", color).render(
+                        &Diagnostic::error(format!("unknown command `{}`", command)).with_help("type :help for a list"))),
+                }
+                continue;
+            }
+        }
+
+        if !line.trim().is_empty() {
+            history.add(line);
+        }
+        // A blank line submits whatever has been typed, complete or not.
+        let submit = line.trim().is_empty();
+        entry.push_str(line);
+        entry.push('\n');
+        if submit || !incomplete(&entry) {
+            session.eval(&std::mem::take(&mut entry));
+        }
+    }
+}
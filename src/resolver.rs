@@ -0,0 +1,203 @@
+// src/resolver.rs
+//
+// Name resolution: runs between parsing and codegen and assigns every
+// variable a numbered slot, so the VM indexes vectors instead of hashing
+// names. Flux scoping is per function: anything a `course`/`purpose`
+// declares (parameters, `mutable`, `constant`, `iterate` variables) is a
+// local of that call; every other name is a global.
+use crate::ast::*;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Global(u16),
+    Local(u16),
+}
+
+/// Local slots of one function. Parameters occupy slots `0..arity` in order.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    slots: HashMap<String, u16>,
+    names: Vec<String>,
+}
+
+impl Scope {
+    /// Locals of a `course`/`purpose` with these parameters and body.
+    pub fn for_function(params: &[Param], body: &[Stmt]) -> Self {
+        let mut scope = Scope::default();
+        for p in params { scope.declare(&p.name); }
+        Self::declare_locals(body, &mut scope);
+        scope
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.slots.contains_key(name)
+    }
+
+    fn declare(&mut self, name: &str) {
+        if !self.slots.contains_key(name) {
+            self.slots.insert(name.to_string(), self.names.len() as u16);
+            self.names.push(name.to_string());
+        }
+    }
+
+    fn declare_locals(body: &[Stmt], scope: &mut Scope) {
+        for s in body {
+            match &s.kind {
+                StmtKind::Const { name, .. } | StmtKind::Mutable { name, .. } => scope.declare(name),
+                StmtKind::Iterate { var, index, body, .. } => {
+                    scope.declare(var);
+                    if let Some(index) = index { scope.declare(index); }
+                    Self::declare_locals(body, scope);
+                }
+                StmtKind::Persist { body, .. } | StmtKind::Block(body) => Self::declare_locals(body, scope),
+                StmtKind::When { then, elifs, otherwise, .. } => {
+                    Self::declare_locals(then, scope);
+                    for (_, b) in elifs { Self::declare_locals(b, scope); }
+                    Self::declare_locals(otherwise, scope);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Declared names in slot order.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn slot_count(&self) -> usize {
+        self.names.len()
+    }
+}
+
+#[derive(Default)]
+pub struct Resolver {
+    globals: HashMap<String, u16>,
+    global_names: Vec<String>,
+    functions: HashMap<String, Scope>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assign slots for every name in `program`. Global slots are stable
+    /// across calls, so one resolver can serve several compilations.
+    pub fn resolve(&mut self, program: &[Stmt]) {
+        for s in program {
+            if let StmtKind::Course { name, params, body, .. } | StmtKind::Purpose { name, params, body, .. } = &s.kind {
+                self.functions.insert(name.clone(), Scope::for_function(params, body));
+            }
+        }
+        for s in program {
+            match &s.kind {
+                StmtKind::Course { name, body, .. } | StmtKind::Purpose { name, body, .. } => {
+                    let scope = self.functions[name].clone();
+                    self.stmts(body, Some(&scope));
+                }
+                _ => self.stmt(s, None),
+            }
+        }
+    }
+
+    /// Global names in slot order.
+    pub fn globals(&self) -> &[String] {
+        &self.global_names
+    }
+
+    pub fn lookup(&self, function: Option<&str>, name: &str) -> Option<Slot> {
+        let local = function
+            .and_then(|f| self.functions.get(f))
+            .and_then(|scope| scope.slots.get(name));
+        match local {
+            Some(&slot) => Some(Slot::Local(slot)),
+            None => self.globals.get(name).map(|&slot| Slot::Global(slot)),
+        }
+    }
+
+    pub fn scope(&self, function: &str) -> Option<&Scope> {
+        self.functions.get(function)
+    }
+
+    fn global(&mut self, name: &str) {
+        if !self.globals.contains_key(name) {
+            self.globals.insert(name.to_string(), self.global_names.len() as u16);
+            self.global_names.push(name.to_string());
+        }
+    }
+
+    /// Give a global slot to `name` unless the enclosing function owns it.
+    fn name(&mut self, name: &str, scope: Option<&Scope>) {
+        if !scope.is_some_and(|s| s.contains(name)) {
+            self.global(name);
+        }
+    }
+
+    fn stmts(&mut self, body: &[Stmt], scope: Option<&Scope>) {
+        for s in body { self.stmt(s, scope); }
+    }
+
+    fn stmt(&mut self, s: &Stmt, scope: Option<&Scope>) {
+        match &s.kind {
+            StmtKind::Const { name, value, .. } => {
+                self.expr(value, scope);
+                self.name(name, scope);
+            }
+            StmtKind::Mutable { name, init, .. } => {
+                if let Some(e) = init { self.expr(e, scope); }
+                self.name(name, scope);
+            }
+            StmtKind::Assign { name, value } => {
+                self.expr(value, scope);
+                self.name(name, scope);
+            }
+            StmtKind::Expr(e) | StmtKind::Return(Some(e)) => self.expr(e, scope),
+            StmtKind::Return(None) | StmtKind::Course { .. } | StmtKind::Purpose { .. } => {}
+            StmtKind::Persist { cond, body } => {
+                self.expr(cond, scope);
+                self.stmts(body, scope);
+            }
+            StmtKind::When { cond, then, elifs, otherwise } => {
+                self.expr(cond, scope);
+                self.stmts(then, scope);
+                for (c, b) in elifs {
+                    self.expr(c, scope);
+                    self.stmts(b, scope);
+                }
+                self.stmts(otherwise, scope);
+            }
+            StmtKind::Iterate { var, index, iterable, body } => {
+                self.expr(iterable, scope);
+                self.name(var, scope);
+                if let Some(index) = index { self.name(index, scope); }
+                self.stmts(body, scope);
+            }
+            StmtKind::Block(body) => self.stmts(body, scope),
+        }
+    }
+
+    fn expr(&mut self, e: &Expr, scope: Option<&Scope>) {
+        match &e.kind {
+            ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Str(_) | ExprKind::Bool(_) => {}
+            ExprKind::Ident(name) => self.name(name, scope),
+            ExprKind::List(elements) | ExprKind::Interp(elements) => {
+                for el in elements { self.expr(el, scope); }
+            }
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left, scope);
+                self.expr(right, scope);
+            }
+            ExprKind::Unary { expr, .. } => self.expr(expr, scope),
+            ExprKind::Call { args, .. } => {
+                for a in args { self.expr(a, scope); }
+            }
+            ExprKind::Index { target, index, value } => {
+                self.expr(target, scope);
+                self.expr(index, scope);
+                if let Some(v) = value { self.expr(v, scope); }
+            }
+        }
+    }
+}
@@ -0,0 +1,368 @@
+// src/semantic.rs
+//
+// Static checks that run after parsing and before codegen. Scoping follows
+// the resolver: top-level names are globals, and anything a `course` or
+// `purpose` declares is local to it. A variable can only be read after its
+// declaration; courses and purposes can be called from anywhere.
+use crate::ast::*;
+use crate::codegen::FuncKind;
+use crate::error::FluxError;
+use crate::natives::{Arity, Natives};
+use crate::resolver::Scope;
+use crate::span::Span;
+use std::collections::{HashMap, HashSet};
+
+struct FunctionSig {
+    kind: FuncKind,
+    arity: usize,
+    span: Span,
+}
+
+/// Check `program` and return every error found. Calls that don't name a
+/// course or purpose must name one of `natives`.
+pub fn check(program: &[Stmt], natives: &Natives) -> Vec<FluxError> {
+    let natives = natives.iter().map(|(name, native)| (name.to_string(), native.arity)).collect();
+    let mut checker = Checker { natives, ..Checker::default() };
+    checker.program(program);
+    let mut errors = checker.errors;
+    errors.sort_by_key(|e| e.span().map(|s| s.start));
+    errors
+}
+
+#[derive(Default)]
+struct Checker {
+    errors: Vec<FluxError>,
+    /// Top-level constants and where they were declared.
+    global_consts: HashMap<String, Span>,
+    /// Constants declared in the function being checked.
+    local_consts: HashMap<String, Span>,
+    /// Locals of the function being checked; `None` at top level.
+    scope: Option<Scope>,
+    /// Names declared anywhere at top level.
+    globals: Scope,
+    /// Variables declared so far in the scope being checked, in statement
+    /// order: a name read before its declaration is still undefined.
+    declared: HashSet<String>,
+    functions: HashMap<String, FunctionSig>,
+    natives: HashMap<String, Arity>,
+    /// Kind and name of the function being checked.
+    current: Option<(FuncKind, String)>,
+}
+
+impl Checker {
+    fn program(&mut self, program: &[Stmt]) {
+        let top_level: Vec<&Stmt> = program.iter()
+            .filter(|s| !matches!(s.kind, StmtKind::Course { .. } | StmtKind::Purpose { .. }))
+            .collect();
+        let mut consts = HashMap::new();
+        for s in &top_level {
+            self.collect_consts(std::slice::from_ref(*s), &mut consts);
+        }
+        self.global_consts = consts;
+        self.globals = Scope::for_function(&[], program);
+
+        for s in program {
+            if let StmtKind::Course { name, params, .. } | StmtKind::Purpose { name, params, .. } = &s.kind {
+                let sig = FunctionSig { kind: Self::kind(s), arity: params.len(), span: s.span };
+                if let Some(first) = self.functions.get(name) {
+                    self.errors.push(FluxError::semantic("E0012", format!("`{}` is defined more than once", name), s.span)
+                        .with_label(first.span, "first defined here"));
+                } else {
+                    self.functions.insert(name.clone(), sig);
+                }
+            }
+        }
+
+        for s in &top_level {
+            self.stmt(s);
+        }
+
+        for s in program {
+            if let StmtKind::Course { name, params, body, .. } | StmtKind::Purpose { name, params, body, .. } = &s.kind {
+                self.current = Some((Self::kind(s), name.clone()));
+                self.function(s.span, params, body);
+                self.current = None;
+            }
+        }
+    }
+
+    fn kind(s: &Stmt) -> FuncKind {
+        if matches!(s.kind, StmtKind::Course { .. }) { FuncKind::Course } else { FuncKind::Purpose }
+    }
+
+    fn function(&mut self, span: Span, params: &[Param], body: &[Stmt]) {
+        let mut consts = HashMap::new();
+        self.collect_consts(body, &mut consts);
+        for p in params.iter().map(|p| &p.name) {
+            if let Some(&decl) = self.global_consts.get(p) {
+                self.shadowed(p, span, decl, "parameter");
+            }
+            if let Some(&decl) = consts.get(p) {
+                self.errors.push(FluxError::semantic("E0006", format!("constant `{}` redeclares a parameter", p), decl)
+                    .with_label(span, format!("parameter `{}` declared here", p)));
+            }
+        }
+        self.local_consts = consts;
+        self.scope = Some(Scope::for_function(params, body));
+        self.declared = params.iter().map(|p| p.name.clone()).collect();
+        for s in body {
+            self.stmt(s);
+        }
+        self.scope = None;
+        self.declared.clear();
+        self.local_consts.clear();
+    }
+
+    /// Gather `constant` declarations in one scope, rejecting duplicates.
+    fn collect_consts(&mut self, body: &[Stmt], out: &mut HashMap<String, Span>) {
+        for s in body {
+            match &s.kind {
+                StmtKind::Const { name, .. } => {
+                    if let Some(&first) = out.get(name) {
+                        self.errors.push(FluxError::semantic("E0006", format!("constant `{}` is declared twice", name), s.span)
+                            .with_label(first, "first declared here"));
+                    } else {
+                        out.insert(name.clone(), s.span);
+                    }
+                }
+                StmtKind::Iterate { body, .. } | StmtKind::Persist { body, .. } | StmtKind::Block(body) => {
+                    self.collect_consts(body, out);
+                }
+                StmtKind::When { then, elifs, otherwise, .. } => {
+                    self.collect_consts(then, out);
+                    for (_, b) in elifs { self.collect_consts(b, out); }
+                    self.collect_consts(otherwise, out);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The constant declaration `name` refers to in the current scope, if any.
+    fn constant(&self, name: &str) -> Option<Span> {
+        match &self.scope {
+            Some(scope) if scope.contains(name) => self.local_consts.get(name).copied(),
+            _ => self.global_consts.get(name).copied(),
+        }
+    }
+
+    fn shadowed(&mut self, name: &str, span: Span, decl: Span, what: &str) {
+        self.errors.push(FluxError::semantic("E0006", format!("{} `{}` shadows a constant", what, name), span)
+            .with_label(decl, format!("`{}` declared as constant here", name))
+            .with_help("constants can't be redeclared; pick a different name"));
+    }
+
+    /// A `mutable` or loop variable named `name` is being introduced.
+    fn declare(&mut self, name: &str, span: Span, what: &str) {
+        // Inside a function the new local hides any global constant.
+        let decl = self.local_consts.get(name).copied()
+            .or_else(|| self.global_consts.get(name).copied());
+        if let Some(decl) = decl {
+            self.shadowed(name, span, decl, what);
+        }
+    }
+
+    fn is_variable(&self, name: &str) -> bool {
+        match &self.scope {
+            Some(scope) if scope.contains(name) => self.declared.contains(name),
+            // A function can be called after any top-level declaration.
+            Some(_) => self.globals.contains(name),
+            None => self.declared.contains(name),
+        }
+    }
+
+    /// Closest known name to `name`, for "did you mean" help.
+    fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+        candidates
+            .map(|c| (edit_distance(name, c), c))
+            .filter(|&(d, c)| d > 0 && d <= (name.len().max(c.len()) / 3).max(1))
+            .min()
+            .map(|(_, c)| c)
+    }
+
+    fn undefined_variable(&mut self, name: &str, span: Span, write: bool) {
+        let message = if write {
+            format!("cannot assign to undeclared variable `{}`", name)
+        } else {
+            format!("undefined variable `{}`", name)
+        };
+        let mut err = FluxError::semantic("E0007", message, span);
+        let locals = self.scope.iter().flat_map(|s| s.names());
+        let known: Vec<&str> = locals.chain(self.globals.names()).map(String::as_str).collect();
+        if let Some(sig) = self.functions.get(name) {
+            err = err.with_help(format!("`{}` is a {}; call it with `{}(...)`", name, sig.kind, name));
+        } else if self.scope.as_ref().map_or(self.globals.contains(name), |s| s.contains(name)) {
+            err = err.with_help(format!("`{}` is declared further down; move the declaration before this", name));
+        } else if let Some(similar) = Self::suggest(name, known.into_iter()) {
+            err = err.with_help(format!("a variable with a similar name exists: `{}`", similar));
+        } else if write {
+            err = err.with_help(format!("declare it first with `mutable {} = ...;`", name));
+        }
+        self.errors.push(err);
+    }
+
+    fn call(&mut self, callee: &str, argc: usize, span: Span, used: bool) {
+        let Some(sig) = self.functions.get(callee) else {
+            if let Some(&arity) = self.natives.get(callee) {
+                if let Arity::Fixed(expected) = arity {
+                    if expected != argc {
+                        self.errors.push(FluxError::semantic("E0009", arity_message(callee, "built-in", expected, argc), span));
+                    }
+                }
+                return;
+            }
+            let names = self.functions.keys().chain(self.natives.keys()).map(String::as_str);
+            let mut err = FluxError::semantic("E0008", format!("undefined function `{}`", callee), span);
+            if let Some(similar) = Self::suggest(callee, names) {
+                err = err.with_help(format!("a function with a similar name exists: `{}`", similar));
+            }
+            self.errors.push(err);
+            return;
+        };
+        if sig.arity != argc {
+            let err = FluxError::semantic("E0009", arity_message(callee, &sig.kind.to_string(), sig.arity, argc), span)
+                .with_label(sig.span, format!("`{}` defined here", callee));
+            self.errors.push(err);
+        }
+        if used && sig.kind == FuncKind::Course {
+            let err = FluxError::semantic("E0011", format!("course `{}` doesn't produce a value", callee), span)
+                .with_label(sig.span, format!("`{}` defined here", callee))
+                .with_help("make it a `purpose` and `yield` the result");
+            self.errors.push(err);
+        }
+    }
+
+    /// Walk an expression. `used` is false only for the outermost expression
+    /// of an expression statement, whose value is discarded.
+    fn expr(&mut self, e: &Expr, used: bool) {
+        match &e.kind {
+            ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Str(_) | ExprKind::Bool(_) => {}
+            ExprKind::Ident(name) => {
+                if !self.is_variable(name) {
+                    self.undefined_variable(name, e.span, false);
+                }
+            }
+            ExprKind::List(elements) | ExprKind::Interp(elements) => {
+                for el in elements { self.expr(el, true); }
+            }
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left, true);
+                self.expr(right, true);
+            }
+            ExprKind::Unary { expr, .. } => self.expr(expr, true),
+            ExprKind::Call { callee, args } => {
+                for a in args { self.expr(a, true); }
+                self.call(callee, args.len(), e.span, used);
+            }
+            ExprKind::Index { target, index, value } => {
+                self.expr(target, true);
+                self.expr(index, true);
+                if let Some(v) = value { self.expr(v, true); }
+            }
+        }
+    }
+
+    fn stmts(&mut self, body: &[Stmt]) {
+        for s in body { self.stmt(s); }
+    }
+
+    fn stmt(&mut self, s: &Stmt) {
+        match &s.kind {
+            StmtKind::Const { name, value, .. } => {
+                self.expr(value, true);
+                self.declared.insert(name.clone());
+                // Duplicates were reported while collecting; here only a local
+                // constant hiding a global one is left to catch.
+                if self.scope.is_some() {
+                    if let Some(&decl) = self.global_consts.get(name) {
+                        self.shadowed(name, s.span, decl, "constant");
+                    }
+                }
+            }
+            StmtKind::Mutable { name, init, .. } => {
+                if let Some(init) = init { self.expr(init, true); }
+                self.declare(name, s.span, "variable");
+                self.declared.insert(name.clone());
+            }
+            StmtKind::Assign { name, value } => {
+                self.expr(value, true);
+                if !self.is_variable(name) {
+                    self.undefined_variable(name, s.span, true);
+                }
+                if let Some(decl) = self.constant(name) {
+                    let is_element = matches!(value.kind, ExprKind::Index { value: Some(_), .. });
+                    let message = if is_element {
+                        format!("cannot modify an element of constant `{}`", name)
+                    } else {
+                        format!("cannot assign to constant `{}`", name)
+                    };
+                    self.errors.push(FluxError::semantic("E0005", message, s.span)
+                        .with_label(decl, format!("`{}` declared as constant here", name))
+                        .with_help(format!("declare `{}` with `mutable` if it needs to change", name)));
+                }
+            }
+            StmtKind::Iterate { var, index, iterable, body } => {
+                self.expr(iterable, true);
+                self.declare(var, s.span, "loop variable");
+                self.declared.insert(var.clone());
+                if let Some(index) = index {
+                    self.declare(index, s.span, "loop variable");
+                    self.declared.insert(index.clone());
+                }
+                self.stmts(body);
+            }
+            StmtKind::Persist { cond, body } => {
+                self.expr(cond, true);
+                self.stmts(body);
+            }
+            StmtKind::Block(body) => self.stmts(body),
+            StmtKind::When { cond, then, elifs, otherwise } => {
+                self.expr(cond, true);
+                self.stmts(then);
+                for (c, b) in elifs {
+                    self.expr(c, true);
+                    self.stmts(b);
+                }
+                self.stmts(otherwise);
+            }
+            StmtKind::Expr(e) => self.expr(e, false),
+            StmtKind::Return(value) => {
+                if let Some(v) = value { self.expr(v, true); }
+                match (&self.current, value) {
+                    (None, _) => self.errors.push(
+                        FluxError::semantic("E0010", "`yield` outside of a course or purpose", s.span)),
+                    (Some((FuncKind::Course, name)), Some(_)) => {
+                        let err = FluxError::semantic("E0010", format!("`yield` with a value inside course `{}`", name), s.span)
+                            .with_help("courses don't return values; use a `purpose`, or write `yield;`");
+                        self.errors.push(err);
+                    }
+                    _ => {}
+                }
+            }
+            StmtKind::Course { .. } | StmtKind::Purpose { .. } => {}
+        }
+    }
+}
+
+pub fn arity_message(name: &str, what: &str, expected: usize, given: usize) -> String {
+    format!(
+        "{} `{}` takes {} argument{} but {} {} given",
+        what, name, expected, if expected == 1 { "" } else { "s" },
+        given, if given == 1 { "was" } else { "were" },
+    )
+}
+
+/// Levenshtein distance between two names.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
@@ -0,0 +1,666 @@
+// src/types.rs
+//
+// Type inference and checking. Every binding, parameter and purpose result
+// starts as a type variable; statements and expressions add constraints that
+// are solved by unification, and a constraint that can't be met (a number
+// added to a list, a string stored in a `[number]`) is reported before the
+// program runs. Annotations fix a type up front and are never widened, but
+// an unannotated variable, result or list that is given values of different
+// types just becomes `any`: that is fine at runtime, so it is fine here.
+//
+// Purposes are checked in definition order, before the top-level code, and
+// generalized afterwards, so `purpose first(xs) { yield xs[0]; }` can be
+// called on lists of any element type.
+use crate::ast::*;
+use crate::codegen::FuncKind;
+use crate::error::FluxError;
+use crate::resolver::Scope;
+use crate::span::Span;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Number,
+    Float,
+    Str,
+    Boolean,
+    Void,
+    List(Box<Type>),
+    /// No particular type: fits anywhere, and anything fits it.
+    Any,
+    /// Index into `Checker::vars`.
+    Var(usize),
+}
+
+impl Type {
+    fn is_numeric(&self) -> bool {
+        matches!(self, Type::Number | Type::Float)
+    }
+}
+
+impl From<&TypeName> for Type {
+    fn from(name: &TypeName) -> Self {
+        match name {
+            TypeName::Number => Type::Number,
+            TypeName::Float => Type::Float,
+            TypeName::Str => Type::Str,
+            TypeName::Boolean => Type::Boolean,
+            TypeName::Void => Type::Void,
+            TypeName::List(element) => Type::List(Box::new(Type::from(&**element))),
+        }
+    }
+}
+
+#[derive(Default)]
+struct VarState {
+    bound: Option<Type>,
+    /// Used as an arithmetic operand, so it can only become a number.
+    numeric: bool,
+    /// Bound by an annotation; `number` is never widened to `float`.
+    fixed: bool,
+}
+
+/// What a name refers to: its type, plus the annotation or declaration
+/// that introduced it, for error labels.
+#[derive(Clone)]
+struct Binding {
+    ty: Type,
+    ann: Option<Span>,
+    decl: Option<Span>,
+}
+
+impl Binding {
+    fn new(ty: Type, decl: Option<Span>) -> Self {
+        Binding { ty, ann: None, decl }
+    }
+}
+
+struct FnType {
+    kind: FuncKind,
+    span: Span,
+    params: Vec<Binding>,
+    ret: Binding,
+    /// Variables replaced by fresh ones at every call, once the body has
+    /// been checked.
+    generic: Vec<usize>,
+}
+
+/// Infer types for `program`. Returns the inferred signature of every
+/// purpose, in definition order, and every error found.
+pub fn check(program: &[Stmt]) -> (Vec<String>, Vec<FluxError>) {
+    let mut checker = Checker::default();
+    checker.program(program);
+    let signatures = program.iter().filter_map(|s| match &s.kind {
+        StmtKind::Purpose { name, params, .. } => Some(checker.signature(name, params)),
+        _ => None,
+    }).collect();
+    let mut errors = checker.errors;
+    errors.sort_by_key(|e| e.span().map(|s| s.start));
+    (signatures, errors)
+}
+
+#[derive(Default)]
+struct Checker {
+    errors: Vec<FluxError>,
+    vars: Vec<VarState>,
+    globals: HashMap<String, Binding>,
+    /// Locals of the function being checked; empty at top level.
+    locals: HashMap<String, Binding>,
+    functions: HashMap<String, FnType>,
+    /// Name of the function being checked and its first `yield` with a value.
+    current: Option<(String, Option<Span>)>,
+}
+
+impl Checker {
+    fn fresh(&mut self) -> Type {
+        self.vars.push(VarState::default());
+        Type::Var(self.vars.len() - 1)
+    }
+
+    /// Binding for a declaration: the annotated type, or a fresh variable.
+    fn binding(&mut self, ann: &Option<TypeAnn>, decl: Span) -> Binding {
+        let ty = match ann {
+            Some(a) => Type::from(&a.node),
+            None => self.fresh(),
+        };
+        Binding { ty, ann: ann.as_ref().map(|a| a.span), decl: Some(decl) }
+    }
+
+    fn program(&mut self, program: &[Stmt]) {
+        for name in Scope::for_function(&[], program).names() {
+            let ty = self.fresh();
+            self.globals.insert(name.clone(), Binding::new(ty, None));
+        }
+        for s in program {
+            if let StmtKind::Course { name, params, ret, .. } | StmtKind::Purpose { name, params, ret, .. } = &s.kind {
+                let kind = if matches!(s.kind, StmtKind::Course { .. }) { FuncKind::Course } else { FuncKind::Purpose };
+                if let (FuncKind::Course, Some(r)) = (kind, ret) {
+                    if r.node != TypeName::Void {
+                        self.errors.push(FluxError::semantic("E0013", format!("course `{}` can't return `{}`", name, r.node), r.span)
+                            .with_help("courses don't return values; make it a `purpose`, or annotate it `void`"));
+                    }
+                }
+                let params = params.iter().map(|p| self.binding(&p.ty, s.span)).collect();
+                let ret = match kind {
+                    FuncKind::Course => Binding::new(Type::Void, None),
+                    FuncKind::Purpose => self.binding(ret, s.span),
+                };
+                let sig = FnType { kind, span: s.span, params, ret, generic: vec![] };
+                self.functions.entry(name.clone()).or_insert(sig);
+            }
+        }
+
+        for s in program {
+            if let StmtKind::Course { name, params, body, .. } | StmtKind::Purpose { name, params, body, .. } = &s.kind {
+                // A duplicate definition was already reported; skip it.
+                if self.functions[name].span == s.span {
+                    self.function(name, params, body);
+                }
+            }
+        }
+
+        for s in program {
+            if !matches!(s.kind, StmtKind::Course { .. } | StmtKind::Purpose { .. }) {
+                self.stmt(s);
+            }
+        }
+    }
+
+    fn function(&mut self, name: &str, params: &[Param], body: &[Stmt]) {
+        for local in Scope::for_function(params, body).names() {
+            let ty = self.fresh();
+            self.locals.insert(local.clone(), Binding::new(ty, None));
+        }
+        for (p, binding) in params.iter().zip(self.functions[name].params.clone()) {
+            self.locals.insert(p.name.clone(), binding);
+        }
+        self.current = Some((name.to_string(), None));
+        self.stmts(body);
+
+        let sig = &self.functions[name];
+        let (kind, ret) = (sig.kind, sig.ret.ty.clone());
+        let yielded = matches!(self.current, Some((_, Some(_))));
+        if kind == FuncKind::Purpose && !yielded {
+            // Never yields a value: the result is always null.
+            self.unify(&ret, &Type::Void);
+        }
+        self.current = None;
+        self.locals.clear();
+
+        // Generalize over the variables that no global can still constrain.
+        let mut in_globals = vec![];
+        for b in self.globals.values() {
+            self.free_vars(&b.ty, &mut in_globals);
+        }
+        let sig = &self.functions[name];
+        let mut generic = vec![];
+        for b in sig.params.iter().chain([&sig.ret]) {
+            self.free_vars(&b.ty, &mut generic);
+        }
+        generic.retain(|v| !in_globals.contains(v));
+        if let Some(sig) = self.functions.get_mut(name) {
+            sig.generic = generic;
+        }
+    }
+
+    /// Follow bound variables until reaching a type constructor or an
+    /// unbound variable.
+    fn shallow(&self, t: &Type) -> Type {
+        let mut t = t.clone();
+        while let Type::Var(v) = t {
+            match &self.vars[v].bound {
+                Some(bound) => t = bound.clone(),
+                None => break,
+            }
+        }
+        t
+    }
+
+    /// Substitute every solved variable in `t`.
+    fn resolve(&self, t: &Type) -> Type {
+        match self.shallow(t) {
+            Type::List(element) => Type::List(Box::new(self.resolve(&element))),
+            other => other,
+        }
+    }
+
+    fn free_vars(&self, t: &Type, out: &mut Vec<usize>) {
+        match self.resolve(t) {
+            Type::Var(v) if !out.contains(&v) => out.push(v),
+            Type::List(element) => self.free_vars(&element, out),
+            _ => {}
+        }
+    }
+
+    fn occurs(&self, v: usize, t: &Type) -> bool {
+        let mut vars = vec![];
+        self.free_vars(t, &mut vars);
+        vars.contains(&v)
+    }
+
+    /// The last variable on `t`'s binding chain, if that chain ends in a type.
+    fn bound_var(&self, t: &Type) -> Option<usize> {
+        let mut last = None;
+        let mut t = t.clone();
+        while let Type::Var(v) = t {
+            let Some(bound) = &self.vars[v].bound else { break };
+            last = Some(v);
+            t = bound.clone();
+        }
+        last
+    }
+
+    /// Constrain a value of type `found` to fit where `expected` is needed.
+    /// Whole numbers fit a `float`, and an inferred `number` becomes `float`
+    /// once a fraction is stored into it.
+    fn unify(&mut self, expected: &Type, found: &Type) -> bool {
+        let (e, f) = (self.shallow(expected), self.shallow(found));
+        match (&e, &f) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Var(a), Type::Var(b)) if a == b => true,
+            (Type::Var(a), Type::Var(b)) => {
+                let (a, b) = (*a, *b);
+                self.vars[b].numeric |= self.vars[a].numeric;
+                self.vars[b].fixed |= self.vars[a].fixed;
+                self.vars[a].bound = Some(Type::Var(b));
+                true
+            }
+            (Type::Var(v), t) | (t, Type::Var(v)) => {
+                if self.occurs(*v, t) || (self.vars[*v].numeric && !t.is_numeric()) {
+                    return false;
+                }
+                self.vars[*v].bound = Some(t.clone());
+                true
+            }
+            (Type::Float, Type::Number) => true,
+            (Type::Number, Type::Float) => match self.bound_var(expected) {
+                Some(v) if !self.vars[v].fixed => {
+                    self.vars[v].bound = Some(Type::Float);
+                    true
+                }
+                _ => false,
+            },
+            (Type::List(a), Type::List(b)) => self.unify(a, b),
+            (a, b) => a == b,
+        }
+    }
+
+    /// Unify, reporting a mismatch at `span` with an optional label.
+    fn expect(&mut self, expected: &Type, found: &Type, span: Span, label: Option<(Span, String)>) {
+        if self.unify(expected, found) {
+            return;
+        }
+        let (expected, found) = (self.resolve(expected), self.resolve(found));
+        let mut err = FluxError::semantic("E0013", format!(
+            "mismatched types: expected `{}`, found `{}`", self.show(&expected), self.show(&found)), span);
+        if let Some((at, text)) = label {
+            err = err.with_label(at, text);
+        }
+        if expected == Type::Number && found == Type::Float {
+            err = err.with_help("`number` holds whole numbers; annotate it `float` to allow fractions");
+        }
+        self.errors.push(err);
+    }
+
+    /// Unify a value into an unannotated place such as a variable or a list
+    /// literal. On a mismatch the place's inferred type becomes `any` unless
+    /// an annotation fixed it; returns whether that (or unifying) worked.
+    fn widen(&mut self, expected: &Type, found: &Type) -> bool {
+        if self.unify(expected, found) {
+            return true;
+        }
+        match self.bound_var(expected) {
+            Some(v) if !self.vars[v].fixed => {
+                self.vars[v].bound = Some(Type::Any);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Store a value of type `found` into the variable `name`.
+    fn store(&mut self, name: &str, binding: &Binding, found: &Type, span: Span) {
+        if binding.ann.is_none() && self.widen(&binding.ty, found) {
+            return;
+        }
+        let label = match (binding.ann, binding.decl) {
+            (Some(ann), _) => Some((ann, "expected due to this annotation".to_string())),
+            (None, Some(decl)) => Some((decl, format!("`{}` declared here", name))),
+            (None, None) => None,
+        };
+        self.expect(&binding.ty, found, span, label);
+    }
+
+    fn show(&self, t: &Type) -> String {
+        self.display(t, &mut vec![])
+    }
+
+    /// Render `t`, naming unsolved variables `a`, `b`, ... in order of
+    /// appearance; a variable only ever used as a number prints as `number`.
+    fn display(&self, t: &Type, names: &mut Vec<usize>) -> String {
+        match self.resolve(t) {
+            Type::Number => "number".to_string(),
+            Type::Float => "float".to_string(),
+            Type::Str => "string".to_string(),
+            Type::Boolean => "boolean".to_string(),
+            Type::Void => "void".to_string(),
+            Type::Any => "any".to_string(),
+            Type::List(element) => format!("[{}]", self.display(&element, names)),
+            Type::Var(v) if self.vars[v].numeric => "number".to_string(),
+            Type::Var(v) => {
+                let i = names.iter().position(|&n| n == v).unwrap_or_else(|| {
+                    names.push(v);
+                    names.len() - 1
+                });
+                ((b'a' + (i % 26) as u8) as char).to_string()
+            }
+        }
+    }
+
+    fn signature(&self, name: &str, params: &[Param]) -> String {
+        let sig = &self.functions[name];
+        let mut names = vec![];
+        let params: Vec<String> = params.iter().zip(&sig.params)
+            .map(|(p, b)| format!("{}: {}", p.name, self.display(&b.ty, &mut names)))
+            .collect();
+        format!("purpose {}({}): {}", name, params.join(", "), self.display(&sig.ret.ty, &mut names))
+    }
+
+    /// Copy `t`, replacing the variables in `generic` with fresh ones.
+    fn instantiate(&mut self, t: &Type, generic: &[usize], fresh: &mut HashMap<usize, Type>) -> Type {
+        match self.resolve(t) {
+            Type::Var(v) if generic.contains(&v) => {
+                if let Some(t) = fresh.get(&v) {
+                    return t.clone();
+                }
+                let t = self.fresh();
+                if let Type::Var(new) = t {
+                    self.vars[new].numeric = self.vars[v].numeric;
+                }
+                fresh.insert(v, t.clone());
+                t
+            }
+            Type::List(element) => Type::List(Box::new(self.instantiate(&element, generic, fresh))),
+            other => other,
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Binding {
+        self.locals.get(name).or_else(|| self.globals.get(name)).cloned()
+            .unwrap_or_else(|| Binding::new(Type::Void, None))
+    }
+
+    /// A `mutable`, `constant` or loop variable is being declared. The first
+    /// declaration of a name refines the type its uses already share; a
+    /// later one starts over, since Flux lets a name be declared again.
+    fn declare(&mut self, name: &str, binding: Binding) -> Binding {
+        let old = self.lookup(name);
+        let binding = if old.decl.is_none() {
+            self.unify(&old.ty, &binding.ty);
+            Binding { ty: old.ty, ..binding }
+        } else {
+            binding
+        };
+        if binding.ann.is_some() {
+            if let Some(v) = self.bound_var(&binding.ty) {
+                self.vars[v].fixed = true;
+            }
+        }
+        let table = if self.locals.contains_key(name) { &mut self.locals } else { &mut self.globals };
+        table.insert(name.to_string(), binding.clone());
+        binding
+    }
+
+    /// An operand of an arithmetic operator must be a number.
+    fn numeric(&mut self, t: &Type, op: &crate::lexer::Token, span: Span) -> bool {
+        match self.shallow(t) {
+            Type::Var(v) => {
+                self.vars[v].numeric = true;
+                true
+            }
+            t if t.is_numeric() || t == Type::Any => true,
+            t => {
+                self.errors.push(FluxError::semantic("E0013", format!("cannot apply {} to `{}`", op, self.show(&t)), span)
+                    .with_help("arithmetic operators only work on `number` and `float`"));
+                false
+            }
+        }
+    }
+
+    fn binary(&mut self, op: &crate::lexer::Token, left: Type, right: Type, span: Span) -> Type {
+        match op {
+            crate::lexer::Token::And | crate::lexer::Token::Or |
+            crate::lexer::Token::EqEq | crate::lexer::Token::BangEq => Type::Boolean,
+            crate::lexer::Token::Lt | crate::lexer::Token::Gt |
+            crate::lexer::Token::LtEq | crate::lexer::Token::GtEq | crate::lexer::Token::To => {
+                let (l, r) = (self.shallow(&left), self.shallow(&right));
+                let comparable = |t: &Type| t.is_numeric() || matches!(t, Type::Str | Type::Var(_));
+                let ok = match (&l, &r) {
+                    (Type::Any, _) | (_, Type::Any) => true,
+                    (Type::Var(_), Type::Str) | (Type::Str, Type::Var(_)) => self.unify(&left, &right),
+                    (Type::Var(v), other) | (other, Type::Var(v)) if other.is_numeric() => {
+                        self.vars[*v].numeric = true;
+                        true
+                    }
+                    _ => comparable(&l) && comparable(&r) && (l == Type::Str) == (r == Type::Str),
+                };
+                if !ok {
+                    self.errors.push(FluxError::semantic("E0013", format!("cannot compare `{}` with `{}`", self.show(&l), self.show(&r)), span));
+                }
+                Type::Boolean
+            }
+            _ => {
+                if !self.numeric(&left, op, span) || !self.numeric(&right, op, span) {
+                    return self.fresh();
+                }
+                match (op, self.shallow(&left), self.shallow(&right)) {
+                    (crate::lexer::Token::Power, _, _) => Type::Float,
+                    (_, Type::Any, _) | (_, _, Type::Any) => Type::Any,
+                    (_, Type::Float, _) | (_, _, Type::Float) => Type::Float,
+                    (_, Type::Number, Type::Number) => Type::Number,
+                    (_, Type::Var(_), Type::Var(_)) => {
+                        self.unify(&left, &right);
+                        left
+                    }
+                    (_, Type::Var(_), _) => left,
+                    _ => right,
+                }
+            }
+        }
+    }
+
+    fn call(&mut self, callee: &str, args: &[Expr]) -> Type {
+        let found: Vec<Type> = args.iter().map(|a| self.expr(a)).collect();
+        // Natives carry no types; their results are unconstrained.
+        let Some(sig) = self.functions.get(callee) else { return self.fresh() };
+        let (span, generic) = (sig.span, sig.generic.clone());
+        let bindings: Vec<Binding> = sig.params.iter().chain([&sig.ret]).cloned().collect();
+        let mut fresh = HashMap::new();
+        let mut types: Vec<Type> = bindings.iter().map(|b| self.instantiate(&b.ty, &generic, &mut fresh)).collect();
+        let ret = types.pop().unwrap_or(Type::Void);
+        for ((arg, found), (param, binding)) in args.iter().zip(&found).zip(types.iter().zip(&bindings)) {
+            let label = match binding.ann {
+                Some(ann) => (ann, "expected due to this annotation".to_string()),
+                None => (span, format!("`{}` defined here", callee)),
+            };
+            self.expect(param, found, arg.span, Some(label));
+        }
+        ret
+    }
+
+    fn expr(&mut self, e: &Expr) -> Type {
+        match &e.kind {
+            ExprKind::Int(_) => Type::Number,
+            ExprKind::Float(_) => Type::Float,
+            ExprKind::Str(_) => Type::Str,
+            ExprKind::Interp(parts) => {
+                // Any value can be shown as text.
+                for part in parts { self.expr(part); }
+                Type::Str
+            }
+            ExprKind::Bool(_) => Type::Boolean,
+            ExprKind::Ident(name) => self.lookup(name).ty,
+            ExprKind::List(elements) => {
+                let element = self.fresh();
+                for el in elements {
+                    let ty = self.expr(el);
+                    self.widen(&element, &ty);
+                }
+                Type::List(Box::new(element))
+            }
+            ExprKind::Binary { left, op, right } => {
+                let l = self.expr(left);
+                let r = self.expr(right);
+                self.binary(op, l, r, e.span)
+            }
+            ExprKind::Unary { op, expr } => {
+                let ty = self.expr(expr);
+                if *op == crate::lexer::Token::Not {
+                    return Type::Boolean;
+                }
+                if !self.numeric(&ty, op, e.span) {
+                    return self.fresh();
+                }
+                ty
+            }
+            ExprKind::Call { callee, args } => self.call(callee, args),
+            ExprKind::Index { target, index, value } => {
+                let target_ty = self.expr(target);
+                let index_ty = self.expr(index);
+                self.expect(&Type::Number, &index_ty, index.span, None);
+                let element = self.fresh();
+                if !self.unify(&target_ty, &Type::List(Box::new(element.clone()))) {
+                    let found = self.resolve(&target_ty);
+                    self.errors.push(FluxError::semantic("E0013", format!("cannot index into `{}`", self.show(&found)), target.span));
+                    return element;
+                }
+                // The list's own element type, so a store can widen it.
+                let element = match self.shallow(&target_ty) {
+                    Type::List(inner) => *inner,
+                    _ => element,
+                };
+                if let Some(v) = value {
+                    let found = self.expr(v);
+                    match &target.kind {
+                        ExprKind::Ident(name) => {
+                            let binding = Binding { ty: element.clone(), ..self.lookup(name) };
+                            self.store(name, &binding, &found, v.span);
+                        }
+                        _ => self.expect(&element, &found, v.span, None),
+                    }
+                }
+                element
+            }
+        }
+    }
+
+    /// Type of the items produced by iterating over `e`.
+    fn iterable(&mut self, e: &Expr) -> Type {
+        if let ExprKind::Binary { left, op: crate::lexer::Token::To, right } = &e.kind {
+            for bound in [left, right] {
+                let ty = self.expr(bound);
+                if !self.unify(&Type::Number, &ty) {
+                    let found = self.resolve(&ty);
+                    self.errors.push(FluxError::semantic("E0013", format!("range bounds must be `number`, found `{}`", self.show(&found)), bound.span));
+                }
+            }
+            return Type::Number;
+        }
+        let ty = self.expr(e);
+        match self.shallow(&ty) {
+            Type::Str => Type::Str,
+            Type::List(element) => *element,
+            // Could be a list or a string; either iterates.
+            Type::Var(_) | Type::Any => self.fresh(),
+            other => {
+                self.errors.push(FluxError::semantic("E0013", format!("cannot iterate over `{}`", self.show(&other)), e.span));
+                self.fresh()
+            }
+        }
+    }
+
+    fn stmts(&mut self, body: &[Stmt]) {
+        for s in body { self.stmt(s); }
+    }
+
+    fn stmt(&mut self, s: &Stmt) {
+        match &s.kind {
+            StmtKind::Const { name, ty, value } | StmtKind::Mutable { name, ty, init: Some(value) } => {
+                let found = self.expr(value);
+                let binding = self.binding(ty, s.span);
+                let binding = self.declare(name, binding);
+                self.store(name, &binding, &found, value.span);
+            }
+            StmtKind::Mutable { name, ty, init: None } => {
+                let binding = self.binding(ty, s.span);
+                self.declare(name, binding);
+            }
+            StmtKind::Assign { name, value } => {
+                let found = self.expr(value);
+                if matches!(value.kind, ExprKind::Index { value: Some(_), .. }) {
+                    return; // element writes are checked by `expr`
+                }
+                let binding = self.lookup(name);
+                self.store(name, &binding, &found, value.span);
+            }
+            StmtKind::Expr(e) => { self.expr(e); }
+            StmtKind::Return(value) => {
+                let found = value.as_ref().map(|v| self.expr(v));
+                let Some((name, first)) = self.current.clone() else { return };
+                let sig = &self.functions[&name];
+                if sig.kind == FuncKind::Course {
+                    return;
+                }
+                let ret = sig.ret.clone();
+                let label = match (ret.ann, first) {
+                    (Some(ann), _) => Some((ann, "expected due to this annotation".to_string())),
+                    (None, Some(first)) => Some((first, "return type inferred from this `yield`".to_string())),
+                    (None, None) => None,
+                };
+                let found = found.unwrap_or(Type::Void);
+                let widened = ret.ann.is_none() && self.widen(&ret.ty, &found);
+                match value {
+                    Some(v) => {
+                        if !widened {
+                            self.expect(&ret.ty, &found, v.span, label);
+                        }
+                        self.current = Some((name, first.or(Some(v.span))));
+                    }
+                    None => {
+                        if !widened && !self.unify(&ret.ty, &Type::Void) {
+                            let expected = self.resolve(&ret.ty);
+                            let mut err = FluxError::semantic("E0013", format!("purpose `{}` must yield a `{}`", name, self.show(&expected)), s.span);
+                            if let Some((at, text)) = label {
+                                err = err.with_label(at, text);
+                            }
+                            self.errors.push(err);
+                        }
+                    }
+                }
+            }
+            StmtKind::Iterate { var, index, iterable, body } => {
+                let element = self.iterable(iterable);
+                self.declare(var, Binding::new(element, Some(s.span)));
+                if let Some(index) = index {
+                    self.declare(index, Binding::new(Type::Number, Some(s.span)));
+                }
+                self.stmts(body);
+            }
+            StmtKind::Persist { cond, body } => {
+                self.expr(cond);
+                self.stmts(body);
+            }
+            StmtKind::Block(body) => self.stmts(body),
+            StmtKind::When { cond, then, elifs, otherwise } => {
+                self.expr(cond);
+                self.stmts(then);
+                for (c, b) in elifs {
+                    self.expr(c);
+                    self.stmts(b);
+                }
+                self.stmts(otherwise);
+            }
+            StmtKind::Course { .. } | StmtKind::Purpose { .. } => {}
+        }
+    }
+}
@@ -1,30 +1,90 @@
 // src/vm.rs
-use crate::codegen::{IR, FuncTable};
-use std::collections::HashMap;
-use std::io::{self, Write};
+use crate::bytecode::Program;
+use crate::codegen::{IR, FuncKind};
+use crate::error::{FluxError, TraceFrame};
+use crate::natives::{Arity, Input, Native, Natives, VmCtx};
+use crate::semantic::arity_message;
+use crate::span::Span;
+use std::cmp::Ordering;
+use std::io::{self, BufRead, BufWriter, Cursor, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+/// Resource caps for one `run`. `None` means unlimited.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Instructions executed.
+    pub max_steps: Option<u64>,
+    /// Wall-clock time, checked every few thousand instructions.
+    pub timeout: Option<Duration>,
+    /// Nested `course`/`purpose` calls.
+    pub max_call_depth: Option<usize>,
+    /// Size of any one list or string built: elements plus string bytes,
+    /// nested. Each value is checked as it is built; the total across all
+    /// live values is not tracked.
+    pub max_size: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            timeout: None,
+            max_call_depth: Some(10_000),
+            max_size: None,
+        }
+    }
+}
+
+/// A Flux value, as seen by Rust code embedding the VM.
 #[derive(Clone, Debug, PartialEq)]
-enum Value {
+pub enum Value {
     Int(i64), Float(f64), Str(String), Bool(bool), Null,
     List(Vec<Value>),
 }
 
 impl Value {
     fn truthy(&self) -> bool { !matches!(self, Value::Bool(false) | Value::Null) }
-    fn as_f64(&self) -> f64 {
+
+    /// Flux-level name of the value's type, for error messages.
+    fn type_name(&self) -> &'static str {
         match self {
-            Value::Int(i) => *i as f64,
-            Value::Float(f) => *f,
-            _ => 0.0,
+            Value::Int(_) => "number",
+            Value::Float(_) => "float",
+            Value::Str(_) => "string",
+            Value::Bool(_) => "boolean",
+            Value::Null => "null",
+            Value::List(_) => "list",
         }
     }
-    fn as_int(&self) -> i64 {
+
+    /// Size counted against `Limits::max_size`.
+    fn size(&self) -> usize {
         match self {
-            Value::Int(i) => *i,
-            Value::Float(f) => *f as i64,
+            Value::Str(s) => s.len(),
+            Value::List(items) => items.len() + items.iter().map(Value::size).sum::<usize>(),
             _ => 0,
         }
     }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    fn compare(&self, other: &Value) -> Result<Ordering, String> {
+        match (self, other) {
+            (Value::Str(a), Value::Str(b)) => Ok(a.cmp(b)),
+            (Value::Int(a), Value::Int(b)) => Ok(a.cmp(b)),
+            _ => match (self.as_f64(), other.as_f64()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).ok_or_else(|| "cannot compare NaN".to_string()),
+                _ => Err(format!("cannot compare {} with {}", self.type_name(), other.type_name())),
+            },
+        }
+    }
 }
 
 impl std::fmt::Display for Value {
@@ -47,83 +107,377 @@ impl std::fmt::Display for Value {
     }
 }
 
-fn bin_arith<F1, F2>(a: Value, b: Value, iop: F1, fop: F2) -> Value 
+impl From<i64> for Value {
+    fn from(v: i64) -> Self { Value::Int(v) }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self { Value::Float(v) }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self { Value::Bool(v) }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self { Value::Str(v.to_string()) }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self { Value::Str(v) }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self { Value::List(v.into_iter().map(Into::into).collect()) }
+}
+
+fn bin_arith<F1, F2>(a: Value, b: Value, sym: &str, iop: F1, fop: F2) -> Result<Value, String>
 where 
-    F1: Fn(i64,i64)->i64, 
+    F1: Fn(i64,i64)->Option<i64>, 
     F2: Fn(f64,f64)->f64 
 {
     match (a, b) {
-        (Value::Int(a), Value::Int(b)) => Value::Int(iop(a, b)),
-        (Value::Float(a), Value::Float(b)) => Value::Float(fop(a, b)),
-        (Value::Int(a), Value::Float(b)) => Value::Float(fop(a as f64, b)),
-        (Value::Float(a), Value::Int(b)) => Value::Float(fop(a, b as f64)),
-        _ => Value::Null,
+        (Value::Int(x), Value::Int(y)) => iop(x, y)
+            .map(Value::Int)
+            .ok_or_else(|| format!("integer overflow in `{} {} {}`", x, sym, y)),
+        (Value::Float(a), Value::Float(b)) => Ok(Value::Float(fop(a, b))),
+        (Value::Int(a), Value::Float(b)) => Ok(Value::Float(fop(a as f64, b))),
+        (Value::Float(a), Value::Int(b)) => Ok(Value::Float(fop(a, b as f64))),
+        (a, b) => Err(format!("cannot apply `{}` to {} and {}", sym, a.type_name(), b.type_name())),
     }
 }
 
 impl std::ops::Add for Value { 
-    type Output = Value; 
-    fn add(self, rhs: Value) -> Value { 
-        bin_arith(self, rhs, |a,b| a + b, |a,b| a + b) 
+    type Output = Result<Value, String>; 
+    fn add(self, rhs: Value) -> Self::Output { 
+        bin_arith(self, rhs, "+", |a,b| a.checked_add(b), |a,b| a + b) 
     } 
 }
 
 impl std::ops::Sub for Value { 
-    type Output = Value; 
-    fn sub(self, rhs: Value) -> Value { 
-        bin_arith(self, rhs, |a,b| a - b, |a,b| a - b) 
+    type Output = Result<Value, String>; 
+    fn sub(self, rhs: Value) -> Self::Output { 
+        bin_arith(self, rhs, "-", |a,b| a.checked_sub(b), |a,b| a - b) 
     } 
 }
 
 impl std::ops::Mul for Value { 
-    type Output = Value; 
-    fn mul(self, rhs: Value) -> Value { 
-        bin_arith(self, rhs, |a,b| a * b, |a,b| a * b) 
+    type Output = Result<Value, String>; 
+    fn mul(self, rhs: Value) -> Self::Output { 
+        bin_arith(self, rhs, "*", |a,b| a.checked_mul(b), |a,b| a * b) 
     } 
 }
 
 impl std::ops::Div for Value { 
-    type Output = Value; 
-    fn div(self, rhs: Value) -> Value { 
-        bin_arith(self, rhs, |a,b| a / b, |a,b| a / b) 
+    type Output = Result<Value, String>; 
+    fn div(self, rhs: Value) -> Self::Output { 
+        if let (Value::Int(_), Value::Int(0)) = (&self, &rhs) {
+            return Err("division by zero".to_string());
+        }
+        bin_arith(self, rhs, "/", |a,b| a.checked_div(b), |a,b| a / b) 
     } 
 }
 
+impl std::ops::Rem for Value { 
+    type Output = Result<Value, String>; 
+    fn rem(self, rhs: Value) -> Self::Output { 
+        if let (Value::Int(_), Value::Int(0)) = (&self, &rhs) {
+            return Err("modulo by zero".to_string());
+        }
+        bin_arith(self, rhs, "%", |a,b| a.checked_rem(b), |a,b| a % b) 
+    } 
+}
+
+/// Validate a list index popped off the stack against a list of length `len`.
+fn list_index(index: &Value, len: usize) -> Result<usize, String> {
+    match index {
+        Value::Int(i) if *i >= 0 && (*i as usize) < len => Ok(*i as usize),
+        Value::Int(i) => Err(format!("index {} out of range for list of length {}", i, len)),
+        other => Err(format!("list index must be a number, found {}", other.type_name())),
+    }
+}
+
+/// Loop-private state of one running `iterate`.
+struct LoopIter {
+    source: IterSource,
+    index: i64,
+}
+
+enum IterSource {
+    Items(std::vec::IntoIter<Value>),
+    /// Inclusive range; `next` is `None` once `last` has been produced.
+    Range { next: Option<i64>, last: i64 },
+}
+
+impl LoopIter {
+    fn new(iterable: Value) -> Result<Self, String> {
+        let items = match iterable {
+            Value::List(items) => items,
+            Value::Str(s) => s.chars().map(|c| Value::Str(c.to_string())).collect(),
+            other => return Err(format!("cannot iterate over {}", other.type_name())),
+        };
+        Ok(Self { source: IterSource::Items(items.into_iter()), index: 0 })
+    }
+
+    fn range(from: Value, to: Value) -> Result<Self, String> {
+        match (from, to) {
+            (Value::Int(first), Value::Int(last)) => Ok(Self {
+                source: IterSource::Range { next: (first <= last).then_some(first), last },
+                index: 0,
+            }),
+            (a, b) => Err(format!("range bounds must be numbers, found {} and {}", a.type_name(), b.type_name())),
+        }
+    }
+
+    /// The next `(index, item)` pair, or `None` when exhausted.
+    fn advance(&mut self) -> Option<(i64, Value)> {
+        let item = match &mut self.source {
+            IterSource::Items(items) => items.next()?,
+            IterSource::Range { next, last } => {
+                let current = (*next)?;
+                *next = if current < *last { Some(current + 1) } else { None };
+                Value::Int(current)
+            }
+        };
+        self.index += 1;
+        Some((self.index - 1, item))
+    }
+}
+
+/// An active `course`/`purpose` call with its own variables.
+struct Frame {
+    ret: usize,
+    call_ip: usize,
+    name: String,
+    kind: FuncKind,
+    locals: Vec<Value>,
+    /// Operand stack height below the call's arguments; restored on return.
+    stack_base: usize,
+    /// LoopIter stack height at the call; loops left by `yield` are dropped.
+    iter_base: usize,
+}
+
+/// An output stream that keeps what is written, for `run_captured`.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Capture {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().expect("capture lock")).into_owned()
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("capture lock").extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 pub struct VM {
     stack: Vec<Value>,
-    globals: HashMap<String, Value>,
-    call_stack: Vec<usize>,
+    globals: Vec<Value>,
+    call_stack: Vec<Frame>,
+    iterators: Vec<LoopIter>,
+    pub limits: Limits,
+    /// Native functions programs can call; the built-ins by default.
+    pub natives: Natives,
+    input: Input,
+    /// Buffered; flushed before input is read and when a run ends.
+    output: Box<dyn Write + Send>,
+    error: Box<dyn Write + Send>,
+}
+
+impl Default for VM {
+    fn default() -> Self { Self::new() }
 }
 
 impl VM {
     pub fn new() -> Self { 
         Self { 
             stack: Vec::with_capacity(1024), 
-            globals: HashMap::new(), 
-            call_stack: Vec::new() 
+            globals: Vec::new(), 
+            call_stack: Vec::new(),
+            iterators: Vec::new(),
+            limits: Limits::default(),
+            natives: Natives::default(),
+            input: Input::Stdin,
+            output: Box::new(BufWriter::new(io::stdout())),
+            error: Box::new(io::stderr()),
         } 
     }
 
+    pub fn set_input(&mut self, input: impl BufRead + Send + 'static) {
+        self.input = Input::Reader(Box::new(input));
+    }
+
+    /// Send program output to `output`, buffered like stdout is.
+    pub fn set_output(&mut self, output: impl Write + Send + 'static) {
+        self.output = Box::new(BufWriter::new(output));
+    }
+
+    pub fn set_error(&mut self, error: impl Write + Send + 'static) {
+        self.error = Box::new(error);
+    }
+
+    /// Run `program` with `input` as its stdin and return what it printed,
+    /// along with how the run ended. The VM's own streams are restored after.
+    pub fn run_captured(&mut self, program: &Program, input: &str) -> (String, Result<Value, FluxError>) {
+        let captured = Capture::default();
+        let input = std::mem::replace(&mut self.input, Input::Reader(Box::new(Cursor::new(input.to_string()))));
+        let output = std::mem::replace(&mut self.output, Box::new(captured.clone()));
+        let result = self.run(program);
+        self.input = input;
+        self.output = output;
+        (captured.text(), result)
+    }
+
     fn pop(&mut self) -> Value {
         self.stack.pop().unwrap_or(Value::Null)
     }
 
-    fn pop_n(&mut self, n: usize) -> Vec<Value> {
-        let mut result = Vec::with_capacity(n);
-        for _ in 0..n {
-            result.push(self.pop());
+    /// The top `n` values, in the order they were pushed.
+    fn pop_n(&mut self, n: usize, ip: usize, spans: &[Span]) -> Result<Vec<Value>, FluxError> {
+        match self.stack.len().checked_sub(n) {
+            Some(at) => Ok(self.stack.split_off(at)),
+            None => Err(self.error(format!("corrupt bytecode: {} values needed, {} on the stack", n, self.stack.len()), ip, spans)),
+        }
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
+        Self { limits, ..Self::new() }
+    }
+
+    fn trace(&self, spans: &[Span]) -> Vec<TraceFrame> {
+        self.call_stack.iter().rev()
+            .map(|frame| TraceFrame {
+                kind: frame.kind,
+                name: frame.name.clone(),
+                call_site: spans.get(frame.call_ip).copied().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Build a runtime error for the instruction at `ip`, with the active call trace.
+    fn error(&self, message: String, ip: usize, spans: &[Span]) -> FluxError {
+        let span = spans.get(ip).copied().unwrap_or_default();
+        FluxError::Runtime { message, span, trace: self.trace(spans) }
+    }
+
+    fn limit_error(&self, message: String, ip: usize, spans: &[Span]) -> FluxError {
+        let span = spans.get(ip).copied().unwrap_or_default();
+        FluxError::Limit { message, span, trace: self.trace(spans) }
+    }
+
+    fn check_size(&self, value: &Value, ip: usize, spans: &[Span]) -> Result<(), FluxError> {
+        match self.limits.max_size {
+            Some(max) if value.size() > max => Err(self.limit_error(
+                format!("{} of size {} exceeds the size limit of {}", value.type_name(), value.size(), max), ip, spans)),
+            _ => Ok(()),
         }
-        result.reverse();
-        result
     }
 
-    pub fn run(&mut self, code: &[IR], functions: &FuncTable) {
-        let mut ip = 0;
-        let mut steps = 0;
-        let max_steps = 10_000;
+    /// Run the top level of `program`. Globals set by earlier runs are kept.
+    pub fn run(&mut self, program: &Program) -> Result<Value, FluxError> {
+        self.run_at(program, 0)
+    }
+
+    /// Value of the global `name`, or `None` if `program` has no such global.
+    /// Globals the program hasn't assigned yet read as null.
+    pub fn global(&self, program: &Program, name: &str) -> Option<Value> {
+        let slot = program.global_slot(name)?;
+        Some(self.globals.get(slot).cloned().unwrap_or(Value::Null))
+    }
+
+    pub fn set_global(&mut self, program: &Program, name: &str, value: Value) -> Result<(), FluxError> {
+        let slot = program.global_slot(name)
+            .ok_or_else(|| FluxError::runtime(format!("no global named `{}`", name)))?;
+        if slot >= self.globals.len() {
+            self.globals.resize(slot + 1, Value::Null);
+        }
+        self.globals[slot] = value;
+        Ok(())
+    }
+
+    /// Call the `course` or `purpose` `name` in `program` and return what it
+    /// yields. Run the program first if the function reads its globals.
+    pub fn call(&mut self, program: &Program, name: &str, args: &[Value]) -> Result<Value, FluxError> {
+        let func = program.functions.get(name)
+            .ok_or_else(|| FluxError::runtime(format!("no course or purpose named `{}`", name)))?;
+        if func.arity != args.len() {
+            return Err(FluxError::runtime(arity_message(name, &func.kind.to_string(), func.arity, args.len())));
+        }
+        let natives = self.link(program)?;
+        self.reset();
+        let mut locals = args.to_vec();
+        locals.resize(func.locals, Value::Null);
+        // Returning lands past the end of the code, which ends the run.
+        self.call_stack.push(Frame {
+            ret: program.code.len(),
+            call_ip: usize::MAX,
+            name: name.to_string(),
+            kind: func.kind,
+            locals,
+            stack_base: 0,
+            iter_base: 0,
+        });
+        let result = self.execute(program, &natives, func.entry);
+        self.flush(result)
+    }
+
+    /// Flush buffered output once a run ends, however it ended.
+    fn flush(&mut self, result: Result<Value, FluxError>) -> Result<Value, FluxError> {
+        match (result, self.output.flush()) {
+            (Ok(_), Err(e)) => Err(FluxError::runtime(format!("couldn't write output: {}", e))),
+            (result, _) => result,
+        }
+    }
+
+    /// Look up every native `program` calls in this VM's registry.
+    fn link(&self, program: &Program) -> Result<Vec<Native>, FluxError> {
+        program.natives.iter()
+            .map(|name| self.natives.get(name).cloned().ok_or_else(|| {
+                FluxError::runtime(format!("native function `{}` is not registered with this VM", name))
+            }))
+            .collect()
+    }
+
+    /// Drop what a failed earlier run left behind; globals stay.
+    fn reset(&mut self) {
+        self.stack.clear();
+        self.call_stack.clear();
+        self.iterators.clear();
+    }
+
+    /// Run `code` from `start`, keeping the globals of earlier runs. Returns
+    /// the value yielded at top level, or null.
+    pub(crate) fn run_at(&mut self, program: &Program, start: usize) -> Result<Value, FluxError> {
+        let natives = self.link(program)?;
+        self.reset();
+        let result = self.execute(program, &natives, start);
+        self.flush(result)
+    }
+
+    fn execute(&mut self, program: &Program, natives: &[Native], start: usize) -> Result<Value, FluxError> {
+        let (code, functions, spans) = (&program.code, &program.functions, &program.spans);
+        let mut ip = start;
+        let mut steps: u64 = 0;
+        let started = Instant::now();
         
-        while ip < code.len() && steps < max_steps {
+        while ip < code.len() {
             steps += 1;
+            if self.limits.max_steps.is_some_and(|max| steps > max) {
+                return Err(self.limit_error(format!("step limit of {} exceeded", steps - 1), ip, spans));
+            }
+            if let Some(timeout) = self.limits.timeout {
+                if steps.is_multiple_of(4096) && started.elapsed() > timeout {
+                    return Err(self.limit_error(format!("timed out after {:?}", timeout), ip, spans));
+                }
+            }
             
             match &code[ip] {
                 IR::PushI(n) => self.stack.push(Value::Int(*n)),
@@ -131,48 +485,66 @@ impl VM {
                 IR::PushS(s) => self.stack.push(Value::Str(s.clone())),
                 IR::PushB(b) => self.stack.push(Value::Bool(*b)),
                 IR::PushNull => self.stack.push(Value::Null),
-                IR::Load(name) => {
-                    let v = self.globals.get(name).cloned().unwrap_or(Value::Null);
+                IR::LoadGlobal(slot) => {
+                    let v = self.globals.get(*slot as usize).cloned().unwrap_or(Value::Null);
                     self.stack.push(v);
                 }
-                IR::Store(name) => {
+                IR::StoreGlobal(slot) => {
                     let v = self.pop();
-                    self.globals.insert(name.clone(), v);
-                }
-                IR::Add => { 
-                    let b = self.pop();
-                    let a = self.pop();
-                    let result = a + b;
-                    self.stack.push(result); 
+                    let slot = *slot as usize;
+                    if slot >= self.globals.len() {
+                        self.globals.resize(slot + 1, Value::Null);
+                    }
+                    self.globals[slot] = v;
                 }
-                IR::Sub => { 
-                    let b = self.pop();
-                    let a = self.pop();
-                    self.stack.push(a - b); 
+                // The compiler never emits these out of place, but a loaded
+                // `.flc` file could.
+                IR::LoadLocal(slot) => {
+                    let local = self.call_stack.last().and_then(|frame| frame.locals.get(*slot as usize));
+                    let Some(v) = local.cloned() else {
+                        return Err(self.error(format!("corrupt bytecode: no local slot {} here", slot), ip, spans));
+                    };
+                    self.stack.push(v);
                 }
-                IR::Mul => { 
-                    let b = self.pop();
-                    let a = self.pop();
-                    self.stack.push(a * b); 
+                IR::StoreLocal(slot) => {
+                    let v = self.pop();
+                    let local = self.call_stack.last_mut().and_then(|frame| frame.locals.get_mut(*slot as usize));
+                    let Some(local) = local else {
+                        return Err(self.error(format!("corrupt bytecode: no local slot {} here", slot), ip, spans));
+                    };
+                    *local = v;
                 }
-                IR::Div => { 
+                IR::Pop => { self.pop(); }
+                IR::Add | IR::Sub | IR::Mul | IR::Div | IR::Mod => { 
                     let b = self.pop();
                     let a = self.pop();
-                    self.stack.push(a / b); 
+                    let result = match &code[ip] {
+                        IR::Add => a + b,
+                        IR::Sub => a - b,
+                        IR::Mul => a * b,
+                        IR::Div => a / b,
+                        _ => a % b,
+                    };
+                    let v = result.map_err(|m| self.error(m, ip, spans))?;
+                    self.stack.push(v); 
                 }
-                IR::Mod => {
+                IR::Power => {
                     let b = self.pop();
                     let a = self.pop();
-                    self.stack.push(if let (Value::Int(a), Value::Int(b)) = (a, b) { 
-                        Value::Int(a % b) 
-                    } else { 
-                        Value::Null 
-                    });
+                    match (a.as_f64(), b.as_f64()) {
+                        (Some(x), Some(y)) => self.stack.push(Value::Float(x.powf(y))),
+                        _ => return Err(self.error(format!("cannot apply `**` to {} and {}", a.type_name(), b.type_name()), ip, spans)),
+                    }
                 }
-                IR::Power => {
-                    let b = self.pop().as_f64();
-                    let a = self.pop().as_f64();
-                    self.stack.push(Value::Float(a.powf(b)));
+                IR::Neg => {
+                    let v = match self.pop() {
+                        Value::Int(i) => i.checked_neg().map(Value::Int)
+                            .ok_or_else(|| format!("integer overflow in `-{}`", i)),
+                        Value::Float(f) => Ok(Value::Float(-f)),
+                        other => Err(format!("cannot negate {}", other.type_name())),
+                    };
+                    let v = v.map_err(|m| self.error(m, ip, spans))?;
+                    self.stack.push(v);
                 }
                 IR::Eq => { 
                     let b = self.pop();
@@ -184,38 +556,18 @@ impl VM {
                     let a = self.pop();
                     self.stack.push(Value::Bool(a != b)); 
                 }
-                IR::Lt => { 
-                    let right = self.pop();
-                    let left = self.pop();
-                    let result = left.as_f64() < right.as_f64();
-                    self.stack.push(Value::Bool(result)); 
-                }
-                IR::Gt => { 
+                IR::Lt | IR::Gt | IR::Le | IR::Ge => { 
                     let right = self.pop();
                     let left = self.pop();
-                    self.stack.push(Value::Bool(left.as_f64() > right.as_f64())); 
-                }
-                IR::Le => { 
-                    let right = self.pop();
-                    let left = self.pop();
-                    let result = left.as_f64() <= right.as_f64();
+                    let ord = left.compare(&right).map_err(|m| self.error(m, ip, spans))?;
+                    let result = match &code[ip] {
+                        IR::Lt => ord == Ordering::Less,
+                        IR::Gt => ord == Ordering::Greater,
+                        IR::Le => ord != Ordering::Greater,
+                        _ => ord != Ordering::Less,
+                    };
                     self.stack.push(Value::Bool(result)); 
                 }
-                IR::Ge => { 
-                    let right = self.pop();
-                    let left = self.pop();
-                    self.stack.push(Value::Bool(left.as_f64() >= right.as_f64())); 
-                }
-                IR::And => { 
-                    let b = self.pop().truthy(); 
-                    let a = self.pop().truthy(); 
-                    self.stack.push(Value::Bool(a && b)); 
-                }
-                IR::Or => { 
-                    let b = self.pop().truthy(); 
-                    let a = self.pop().truthy(); 
-                    self.stack.push(Value::Bool(a || b)); 
-                }
                 IR::Not => { 
                     let v = self.pop().truthy(); 
                     self.stack.push(Value::Bool(!v)); 
@@ -231,87 +583,141 @@ impl VM {
                         continue; // Skip ip += 1 if jump is taken
                     } 
                 }
+                IR::JumpTrue(t) => { 
+                    if self.pop().truthy() { 
+                        ip = *t; 
+                        continue;
+                    } 
+                }
                 // *** END JUMP FIXES ***
                 IR::MakeList(size) => {
-                    let elements = self.pop_n(*size);
-                    self.stack.push(Value::List(elements));
+                    let list = Value::List(self.pop_n(*size, ip, spans)?);
+                    self.check_size(&list, ip, spans)?;
+                    self.stack.push(list);
+                }
+                IR::Concat(count) => {
+                    let text: String = self.pop_n(*count, ip, spans)?.iter().map(|v| v.to_string()).collect();
+                    let text = Value::Str(text);
+                    self.check_size(&text, ip, spans)?;
+                    self.stack.push(text);
                 }
                 IR::GetIndex => {
-                    let index = self.pop().as_int() as usize;
-                    if let Value::List(list) = self.pop() {
-                        if index < list.len() {
-                            self.stack.push(list[index].clone());
-                        } else {
-                            self.stack.push(Value::Null);
+                    let index = self.pop();
+                    match self.pop() {
+                        Value::List(list) => {
+                            let i = list_index(&index, list.len()).map_err(|m| self.error(m, ip, spans))?;
+                            self.stack.push(list[i].clone());
                         }
-                    } else {
-                        self.stack.push(Value::Null);
+                        other => return Err(self.error(format!("cannot index into {}", other.type_name()), ip, spans)),
                     }
                 }
                 IR::SetIndex => {
                     let value = self.pop();
-                    let index = self.pop().as_int() as usize;
-                    if let Value::List(mut list) = self.pop() {
-                        if index < list.len() {
-                            list[index] = value;
-                            self.stack.push(Value::List(list));
-                        } else {
-                            self.stack.push(Value::Null);
+                    let index = self.pop();
+                    match self.pop() {
+                        Value::List(mut list) => {
+                            let i = list_index(&index, list.len()).map_err(|m| self.error(m, ip, spans))?;
+                            list[i] = value;
+                            let list = Value::List(list);
+                            self.check_size(&list, ip, spans)?;
+                            self.stack.push(list);
                         }
-                    } else {
-                        self.stack.push(Value::Null);
+                        other => return Err(self.error(format!("cannot index into {}", other.type_name()), ip, spans)),
                     }
                 }
-                IR::ListLen => {
-                    if let Value::List(list) = self.pop() {
-                        self.stack.push(Value::Int(list.len() as i64));
-                    } else {
-                        self.stack.push(Value::Int(0));
+                IR::IterInit => {
+                    let iterable = self.pop();
+                    let iter = LoopIter::new(iterable).map_err(|m| self.error(m, ip, spans))?;
+                    self.iterators.push(iter);
+                }
+                IR::IterRange => {
+                    let to = self.pop();
+                    let from = self.pop();
+                    let iter = LoopIter::range(from, to).map_err(|m| self.error(m, ip, spans))?;
+                    self.iterators.push(iter);
+                }
+                IR::IterNext(exit, with_index) => {
+                    let Some(iter) = self.iterators.last_mut() else {
+                        return Err(self.error("corrupt bytecode: no loop to advance".to_string(), ip, spans));
+                    };
+                    match iter.advance() {
+                        Some((index, item)) => {
+                            if *with_index {
+                                self.stack.push(Value::Int(index));
+                            }
+                            self.stack.push(item);
+                        }
+                        None => {
+                            self.iterators.pop();
+                            ip = *exit;
+                            continue;
+                        }
                     }
                 }
+                IR::CallNative(index, argc) => {
+                    let (name, native) = (&program.natives[*index as usize], &natives[*index as usize]);
+                    if let Arity::Fixed(arity) = native.arity {
+                        if arity != *argc {
+                            return Err(self.error(arity_message(name, "built-in", arity, *argc), ip, spans));
+                        }
+                    }
+                    let args = self.pop_n(*argc, ip, spans)?;
+                    let mut ctx = VmCtx { input: &mut self.input, output: &mut self.output, error: &mut self.error };
+                    let result = match (native.func)(&mut ctx, &args) {
+                        Ok(result) => result,
+                        // Natives don't know where they were called from.
+                        Err(FluxError::Runtime { message, span, .. }) if span == Span::default() => {
+                            return Err(self.error(message, ip, spans));
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    self.check_size(&result, ip, spans)?;
+                    self.stack.push(result);
+                }
                 IR::Call(name, argc) => {
-                    if name == "getInput" {
-                        print!("Input: ");
-                        io::stdout().flush().unwrap();
-                        let mut input = String::new();
-                        io::stdin().read_line(&mut input).unwrap();
-                        
-                        // NOTE: getInput handles its own stack pushing
-                        for _ in 0..*argc {
-                            self.pop(); // Pop arguments that were pushed before the call
+                    if let Some(func) = functions.get(name) {
+                        if func.arity != *argc {
+                            let message = arity_message(name, &func.kind.to_string(), func.arity, *argc);
+                            return Err(self.error(message, ip, spans));
                         }
-                        self.stack.push(Value::Str(input.trim().to_string()));
-                    } else if name == "report" {
-                        let args = self.pop_n(*argc);
-                        for arg in args {
-                            print!("{} ", arg);
+                        if self.limits.max_call_depth.is_some_and(|max| self.call_stack.len() >= max) {
+                            return Err(self.limit_error(format!(
+                                "call depth limit of {} exceeded", self.call_stack.len()), ip, spans));
                         }
-                        println!();
-                        self.stack.push(Value::Null); // report returns null
-                    } else if let Some(&target) = functions.get(name) {
-                        self.call_stack.push(ip + 1);
-                        ip = target;
+                        // Arguments become local slots 0..arity; the rest start as null.
+                        let mut locals = self.pop_n(*argc, ip, spans)?;
+                        locals.resize(func.locals, Value::Null);
+                        self.call_stack.push(Frame {
+                            ret: ip + 1,
+                            call_ip: ip,
+                            name: name.clone(),
+                            kind: func.kind,
+                            locals,
+                            stack_base: self.stack.len(),
+                            iter_base: self.iterators.len(),
+                        });
+                        ip = func.entry;
                         continue;
                     } else {
-                        self.pop_n(*argc);
-                        self.stack.push(Value::Null);
+                        return Err(self.error(format!("undefined function `{}`", name), ip, spans));
                     }
                 }
                 IR::Return => {
-                    if let Some(ret) = self.call_stack.pop() {
-                        ip = ret;
+                    if let Some(frame) = self.call_stack.pop() {
+                        let result = self.pop();
+                        self.stack.truncate(frame.stack_base);
+                        self.iterators.truncate(frame.iter_base);
+                        self.stack.push(result);
+                        ip = frame.ret;
                         continue;
                     } else {
-                        break;
+                        return Ok(self.pop());
                     }
                 }
             }
             ip += 1;
         }
         
-        if steps >= max_steps {
-            // Keep error logging for critical limits
-            eprintln!("Execution stopped: maximum steps exceeded");
-        }
+        Ok(self.pop())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,249 @@
+// tests/api.rs
+//
+// The embedding API: compiling from Rust, calling into a program, reading
+// and writing its globals, and the errors that come back.
+use project_flux::{Arity, FluxError, LexErrorKind, Natives, Value, VM};
+use std::io::{self, BufRead, Read, Write};
+use std::sync::{Arc, Mutex};
+
+const SOURCE: &str = "\
+mutable count = 1;
+constant NAMES = [\"ada\", \"bob\"];
+
+purpose add(a, b) {
+    yield a + b;
+}
+purpose greet(name) {
+    yield [\"hello\", name];
+}
+purpose total(xs) {
+    mutable sum = 0;
+    iterate x across xs {
+        sum = sum + x;
+    }
+    yield sum;
+}
+course bump(by) {
+    count = count + by;
+}
+purpose ratio(a, b) {
+    yield a / b;
+}
+";
+
+fn program() -> project_flux::Program {
+    project_flux::compile("api.fl", SOURCE).expect("compiles")
+}
+
+#[test]
+fn calls_a_purpose_with_rust_arguments() {
+    let program = program();
+    let mut vm = VM::new();
+    assert_eq!(vm.call(&program, "add", &[2.into(), 3.into()]).unwrap(), Value::Int(5));
+    assert_eq!(vm.call(&program, "add", &[2.into(), 0.5.into()]).unwrap(), Value::Float(2.5));
+    assert_eq!(vm.call(&program, "greet", &["flux".into()]).unwrap(), Value::from(vec!["hello", "flux"]));
+    assert_eq!(vm.call(&program, "total", &[vec![1, 2, 3].into()]).unwrap(), Value::Int(6));
+}
+
+#[test]
+fn reads_and_sets_globals() {
+    let program = program();
+    let mut vm = VM::new();
+    // Not run yet: the global exists but holds nothing.
+    assert_eq!(vm.global(&program, "count"), Some(Value::Null));
+    vm.run(&program).expect("runs");
+    assert_eq!(vm.global(&program, "count"), Some(Value::Int(1)));
+    assert_eq!(vm.global(&program, "NAMES"), Some(Value::from(vec!["ada", "bob"])));
+    assert_eq!(vm.global(&program, "missing"), None);
+
+    vm.set_global(&program, "count", 10.into()).expect("`count` is a global");
+    assert_eq!(vm.call(&program, "bump", &[5.into()]).unwrap(), Value::Null);
+    assert_eq!(vm.global(&program, "count"), Some(Value::Int(15)));
+    assert!(vm.set_global(&program, "missing", Value::Null).is_err());
+}
+
+#[test]
+fn converts_values() {
+    assert_eq!(Value::from(7), Value::Int(7));
+    assert_eq!(Value::from(1.5), Value::Float(1.5));
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from("a"), Value::Str("a".to_string()));
+    assert_eq!(Value::from(String::from("b")), Value::Str("b".to_string()));
+    let nested = Value::from(vec![vec![1, 2], vec![3]]);
+    assert_eq!(nested, Value::List(vec![
+        Value::List(vec![Value::Int(1), Value::Int(2)]),
+        Value::List(vec![Value::Int(3)]),
+    ]));
+    assert_eq!(nested.to_string(), "[[1, 2], [3]]");
+    assert_eq!(Value::Null.to_string(), "null");
+}
+
+#[test]
+fn compile_errors_are_structured() {
+    let errors = project_flux::compile("bad.fl", "mutable x = 1;\nreport(y);\n").expect_err("`y` is undefined");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code(), "E0007");
+    assert_eq!(errors[0].span().map(|s| (s.line, s.col)), Some((2, 8)));
+    assert!(errors[0].message().contains("`y`"), "{}", errors[0].message());
+
+    let errors = project_flux::check("report(1 $ 2);").expect_err("`$` isn't a token");
+    assert!(matches!(errors[..], [FluxError::Lex { .. }]));
+}
+
+#[test]
+fn lex_errors_have_a_kind() {
+    let errors = project_flux::check("report(\"a {1 + 2\");").expect_err("the interpolation isn't closed");
+    assert_eq!(errors[0].lex_kind(), Some(LexErrorKind::Unterminated));
+    assert_eq!(errors[0].message(), "Unterminated interpolation");
+    let errors = project_flux::check("report(1 $ 2);").expect_err("`$` isn't a token");
+    assert_eq!(errors[0].lex_kind(), Some(LexErrorKind::Other));
+    assert_eq!(project_flux::check("report(y);").expect_err("`y` is undefined")[0].lex_kind(), None);
+}
+
+#[test]
+fn call_errors_are_structured() {
+    let program = program();
+    let mut vm = VM::new();
+    let err = vm.call(&program, "ratio", &[1.into(), 0.into()]).expect_err("division by zero");
+    assert!(matches!(err, FluxError::Runtime { .. }));
+    assert_eq!(err.code(), "E0003");
+    // The span points at the division inside `ratio`.
+    assert_eq!(err.span().map(|s| s.line), Some(21));
+
+    let err = vm.call(&program, "add", &[1.into()]).expect_err("wrong arity");
+    assert!(err.message().contains("2 arguments"), "{}", err.message());
+    let err = vm.call(&program, "nothing", &[]).expect_err("no such function");
+    assert!(err.message().contains("`nothing`"), "{}", err.message());
+    // A failed call leaves the VM usable.
+    assert_eq!(vm.call(&program, "add", &[1.into(), 1.into()]).unwrap(), Value::Int(2));
+}
+
+#[test]
+fn unregistered_natives_fail_to_link() {
+    let mut natives = Natives::default();
+    natives.register("shout", Arity::Fixed(1), |_, args| Ok(args[0].to_string().to_uppercase().into()));
+    let program = project_flux::compile_with("shout.fl", "mutable loud = shout(\"hi\");", &natives).expect("compiles");
+
+    let err = VM::new().run(&program).expect_err("`shout` isn't registered");
+    assert!(err.message().contains("native function `shout` is not registered"), "{}", err.message());
+
+    let mut vm = VM::new();
+    vm.natives = natives;
+    vm.run(&program).expect("runs");
+    assert_eq!(vm.global(&program, "loud"), Some(Value::from("HI")));
+}
+
+#[test]
+fn registered_natives_are_callable() {
+    let mut natives = Natives::default();
+    natives.register("join", Arity::Variadic, |_, args| {
+        let parts: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        Ok(parts.join("-").into())
+    });
+    let program = project_flux::compile_with("join.fl", "mutable joined = join(1, \"a\", true);", &natives).expect("compiles");
+    let mut vm = VM::new();
+    vm.natives = natives;
+    vm.run(&program).expect("runs");
+    assert_eq!(vm.global(&program, "joined"), Some(Value::from("1-a-true")));
+}
+
+#[test]
+fn fixed_native_arity_is_checked_before_running() {
+    let mut natives = Natives::default();
+    natives.register("shout", Arity::Fixed(1), |_, args| Ok(args[0].to_string().to_uppercase().into()));
+    let errors = project_flux::compile_with("shout.fl", "report(shout(\"a\", \"b\"));", &natives)
+        .expect_err("`shout` takes one argument");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code(), "E0009");
+    assert!(errors[0].message().contains("shout"), "{}", errors[0].message());
+}
+
+#[test]
+fn native_errors_point_at_the_call() {
+    let mut natives = Natives::default();
+    natives.register("fail", Arity::Fixed(0), |_, _| Err(FluxError::runtime("no luck")));
+    let program = project_flux::compile_with("fail.fl", "mutable x = 1;\nx = x + 1;\nfail();\n", &natives).expect("compiles");
+    let mut vm = VM::new();
+    vm.natives = natives;
+    let err = vm.run(&program).expect_err("`fail` fails");
+    assert_eq!(err.message(), "no luck");
+    assert_eq!(err.span().map(|s| (s.line, s.col)), Some((3, 1)));
+    // What ran before the call still happened.
+    assert_eq!(vm.global(&program, "x"), Some(Value::Int(2)));
+}
+
+/// A writer whose bytes the test can still see once the VM owns it.
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Shared {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Input that notes what had been written to `output` by each read.
+struct Watching {
+    lines: io::Cursor<Vec<u8>>,
+    output: Shared,
+    seen: Arc<Mutex<Vec<String>>>,
+}
+
+impl Read for Watching {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.lines.read(buf)
+    }
+}
+
+impl BufRead for Watching {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.seen.lock().unwrap().push(self.output.text());
+        self.lines.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.lines.consume(amount)
+    }
+}
+
+#[test]
+fn runs_with_host_streams() {
+    let program = project_flux::compile("echo.fl", "report(\"name?\");\nmutable name = getInput();\nreport(\"hi\", name);\n")
+        .expect("compiles");
+    let output = Shared::default();
+    let seen = Arc::new(Mutex::new(vec![]));
+    let mut vm = VM::new();
+    vm.set_output(output.clone());
+    vm.set_input(Watching { lines: io::Cursor::new(b"ada\n".to_vec()), output: output.clone(), seen: seen.clone() });
+    vm.run(&program).expect("runs");
+    // Everything before `getInput` had reached the writer by the time it read.
+    assert_eq!(seen.lock().unwrap().first().map(String::as_str), Some("name? \nInput: "));
+    assert_eq!(output.text(), "name? \nInput: hi ada \n");
+}
+
+#[test]
+fn captures_a_run() {
+    let program = project_flux::compile("echo.fl", "report(getInput(), getInput());\n").expect("compiles");
+    let output = Shared::default();
+    let mut vm = VM::new();
+    vm.set_input(io::Cursor::new("three\n"));
+    vm.set_output(output.clone());
+    let (out, result) = vm.run_captured(&program, "one\ntwo\n");
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(out, "Input: Input: one two \n");
+    // The VM's own streams are back in place afterwards.
+    assert_eq!(output.text(), "");
+    vm.run(&program).expect("runs");
+    assert_eq!(output.text(), "Input: Input: three  \n");
+}
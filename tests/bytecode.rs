@@ -0,0 +1,234 @@
+// tests/bytecode.rs
+//
+// The `.flc` format: programs survive `flux compile` -> `flux run`, and
+// damaged files are rejected with a load error or a runtime error, never a
+// panic.
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process::Command;
+
+const SOURCE: &str = "\
+purpose twice(n) {
+    mutable result = n * 2;
+    yield result;
+}
+mutable total = 0;
+iterate x across [1, 2, 3] {
+    total = total + twice(x);
+}
+report(\"total:\", total, 2.5, true);
+";
+
+fn flux(args: &[&str]) -> (i32, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_project_flux")).args(args).output().expect("the binary runs");
+    (
+        output.status.code().expect("exits normally"),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+fn path(name: &str) -> String {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name).display().to_string()
+}
+
+/// SOURCE, compiled to `<name>.flc`.
+fn encoded(name: &str) -> Vec<u8> {
+    let (source, flc) = (path(&format!("{}.fl", name)), path(&format!("{}.flc", name)));
+    std::fs::write(&source, SOURCE).expect("writes the program");
+    let (code, _, stderr) = flux(&["compile", &format!("--output={}", flc), &source]);
+    assert_eq!(code, 0, "{}", stderr);
+    std::fs::read(&flc).expect("reads the bytecode")
+}
+
+/// Run `bytes` as `<name>.flc`; return the exit code, stdout and stderr.
+fn run(name: &str, bytes: &[u8]) -> (i32, String, String) {
+    let flc = path(&format!("{}.flc", name));
+    std::fs::write(&flc, bytes).expect("writes the bytecode");
+    flux(&["run", &flc])
+}
+
+/// The FNV-1a checksum the format ends with.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Where the instructions are, found by walking the header. After them
+/// come a 16-byte span per instruction, then the checksum.
+fn code(bytes: &[u8]) -> Range<usize> {
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().expect("4 bytes")) as usize;
+    // Magic and version, then the source path.
+    let mut at = 6;
+    at += 4 + u32_at(at);
+    // Each constant is a tag byte, then a string or 8 bytes.
+    let constants = u32_at(at);
+    at += 4;
+    for _ in 0..constants {
+        at += 1 + if bytes[at] == 2 { 4 + u32_at(at + 1) } else { 8 };
+    }
+    // Each function is a name, entry, arity, kind and locals.
+    at += 4 + 17 * u32_at(at);
+    // Each global, then each native, is a name.
+    at += 4 + 4 * u32_at(at);
+    at += 4 + 4 * u32_at(at);
+    at + 4..bytes.len() - 8 - 16 * u32_at(at)
+}
+
+/// Recompute the checksum after patching `bytes`.
+fn reseal(bytes: &mut Vec<u8>) {
+    bytes.truncate(bytes.len() - 8);
+    let checksum = fnv1a(bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+}
+
+/// Assert that `bytes` fail to load, with `message` in the error.
+fn rejected(name: &str, bytes: &[u8], message: &str) {
+    let (code, stdout, stderr) = run(name, bytes);
+    assert_eq!(code, 1, "{}", stderr);
+    assert!(stderr.contains(message), "{}", stderr);
+    assert_eq!(stdout, "");
+}
+
+/// Assert that `bytes` load but stop with a runtime error about corrupt bytecode.
+fn corrupt_at_runtime(name: &str, bytes: &[u8]) {
+    let (code, _, stderr) = run(name, bytes);
+    assert_eq!(code, 2, "{}", stderr);
+    assert!(stderr.contains("error[E0003]") && stderr.contains("corrupt bytecode"), "{}", stderr);
+}
+
+#[test]
+fn round_trip() {
+    let bytes = encoded("round_trip");
+    let (code, stdout, stderr) = run("round_trip", &bytes);
+    assert_eq!((code, stderr.as_str()), (0, ""));
+    assert!(stdout.contains("total: 12 2.5 true \n"), "{}", stdout);
+}
+
+#[test]
+fn rejects_other_files() {
+    rejected("script", b"#!/bin/sh\n", "not a Flux bytecode file");
+    rejected("empty", b"", "not a Flux bytecode file");
+}
+
+#[test]
+fn rejects_other_versions() {
+    let mut bytes = encoded("versions");
+    bytes[4..6].copy_from_slice(&u16::MAX.to_le_bytes());
+    reseal(&mut bytes);
+    rejected("versions", &bytes, "bytecode version 65535 is not supported");
+}
+
+#[test]
+fn rejects_bad_checksums() {
+    let mut bytes = encoded("checksums");
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0xFF;
+    rejected("checksums", &bytes, "checksum mismatch");
+}
+
+#[test]
+fn rejects_truncated_files() {
+    let bytes = encoded("truncated");
+    rejected("truncated", &bytes[..8], "truncated");
+    // Cut inside the body: the checksum no longer matches.
+    rejected("truncated", &bytes[..bytes.len() - 20], "couldn't load");
+}
+
+#[test]
+fn rejects_out_of_range_operands() {
+    let mut bytes = encoded("operands");
+    // The first instruction is the `jump` (opcode 24) over the functions to
+    // the top-level code; point it past the end.
+    let at = code(&bytes).start;
+    assert_eq!(bytes[at], 24);
+    bytes[at + 1..at + 5].copy_from_slice(&1000u32.to_le_bytes());
+    reseal(&mut bytes);
+    rejected("operands", &bytes, "jump target 1000 is out of range");
+
+    // `store_global 0` is opcode 6 with a u16 slot; point it past the globals.
+    let mut bytes = encoded("operands");
+    let code = code(&bytes);
+    let at = bytes[code.clone()].windows(3).rposition(|w| w == [6, 0, 0]).expect("a store_global 0") + code.start;
+    bytes[at + 1] = 200;
+    reseal(&mut bytes);
+    rejected("operands", &bytes, "global slot 200 is out of range");
+}
+
+#[test]
+fn local_access_outside_a_call_is_a_runtime_error() {
+    let mut bytes = encoded("locals");
+    // Turn top-level `load_global 0` (opcode 5) into `load_local 0` (7).
+    // Slot 0 exists in `twice`, so the loader's range check passes.
+    let code = code(&bytes);
+    let at = bytes[code.clone()].windows(3).rposition(|w| w == [5, 0, 0]).expect("a load_global 0") + code.start;
+    bytes[at] = 7;
+    reseal(&mut bytes);
+    corrupt_at_runtime("locals", &bytes);
+}
+
+/// Where `twice`'s entry in the function table starts: entry 1, arity 1,
+/// kind purpose, 2 locals.
+fn twice_entry(bytes: &[u8]) -> usize {
+    let record = [1, 0, 0, 0, 1, 0, 0, 0, 1, 2, 0, 0, 0];
+    bytes.windows(record.len()).position(|w| w == record).expect("`twice` in the function table")
+}
+
+/// Where the argument count of `call twice, 1` (opcode 27) is.
+fn call_argc(bytes: &[u8]) -> usize {
+    let code = code(bytes);
+    bytes[code.clone()].windows(9).position(|w| w[0] == 27 && w[5..] == [1, 0, 0, 0]).expect("a call with 1 argument") + code.start + 5
+}
+
+/// Where `make_list 3` (opcode 29) is.
+fn make_list(bytes: &[u8]) -> usize {
+    let code = code(bytes);
+    bytes[code.clone()].windows(5).position(|w| w == [29, 3, 0, 0, 0]).expect("a make_list 3") + code.start
+}
+
+#[test]
+fn rejects_calls_with_the_wrong_argument_count() {
+    let mut bytes = encoded("argc");
+    let argc = call_argc(&bytes);
+    bytes[argc] = 2;
+    reseal(&mut bytes);
+    rejected("argc", &bytes, "call to `twice` with 2 arguments");
+}
+
+#[test]
+fn rejects_counts_past_the_code() {
+    // An arity, locals and argument count of 100 agree, but a program this
+    // short never pushes 100 arguments.
+    let mut bytes = encoded("counts");
+    let (twice, argc) = (twice_entry(&bytes), call_argc(&bytes));
+    bytes[twice + 4] = 100;
+    bytes[twice + 9] = 100;
+    bytes[argc] = 100;
+    reseal(&mut bytes);
+    rejected("counts", &bytes, "operand count 100 is out of range");
+
+    // `make_list 3` asking for four billion elements.
+    let mut bytes = encoded("counts");
+    let at = make_list(&bytes);
+    bytes[at + 1..at + 5].copy_from_slice(&u32::MAX.to_le_bytes());
+    reseal(&mut bytes);
+    rejected("counts", &bytes, "is out of range");
+}
+
+#[test]
+fn rejects_huge_locals() {
+    let mut bytes = encoded("huge_locals");
+    let twice = twice_entry(&bytes);
+    bytes[twice + 9..twice + 13].copy_from_slice(&u32::MAX.to_le_bytes());
+    reseal(&mut bytes);
+    rejected("huge_locals", &bytes, "invalid entry for function `twice`");
+}
+
+#[test]
+fn stack_underflow_is_a_runtime_error() {
+    // `make_list 20` is within the code's size, but only 3 values are pushed.
+    let mut bytes = encoded("underflow");
+    let at = make_list(&bytes);
+    bytes[at + 1] = 20;
+    reseal(&mut bytes);
+    corrupt_at_runtime("underflow", &bytes);
+}
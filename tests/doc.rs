@@ -0,0 +1,117 @@
+// tests/doc.rs
+//
+// `flux doc`: what the reference says about each declaration, its anchors,
+// and the links between declarations, in Markdown and in HTML.
+use project_flux::DocFormat;
+
+const SOURCE: &str = "\
+/// How many times `greet` has run.
+mutable greeted = 0;
+mutable scratch = 1;
+
+/// Largest accepted name length.
+constant LIMIT: number = 10;
+
+/// Says hello.
+///
+/// Uses `shorten` on long names & counts in `greeted`.
+course greet(name: string) {
+    report(\"hello\", shorten(name));
+    greeted = greeted + 1;
+}
+
+purpose shorten(name) {
+    yield name;
+}
+
+greet(\"ada\");
+";
+
+#[test]
+fn markdown_reference() {
+    let markdown = project_flux::doc("prog.fl", SOURCE, DocFormat::Markdown).expect("documents");
+    assert_eq!(markdown, "\
+# prog.fl
+
+- mutable [`greeted`](#mutable-greeted): How many times [`greet`](#course-greet) has run.
+- constant [`LIMIT`](#constant-limit): Largest accepted name length.
+- course [`greet`](#course-greet): Says hello.
+- purpose [`shorten`](#purpose-shorten)
+
+## Purposes
+
+### purpose `shorten`
+
+```flux
+purpose shorten(name)
+```
+
+Called by: [`greet`](#course-greet)
+
+*Defined on line 16.*
+
+## Courses
+
+### course `greet`
+
+```flux
+course greet(name: string)
+```
+
+Says hello.
+
+Uses [`shorten`](#purpose-shorten) on long names & counts in [`greeted`](#mutable-greeted).
+
+Calls: [`shorten`](#purpose-shorten)
+
+*Defined on line 11.*
+
+## Constants
+
+### constant `LIMIT`
+
+```flux
+constant LIMIT: number
+```
+
+Largest accepted name length.
+
+*Defined on line 6.*
+
+## Variables
+
+### mutable `greeted`
+
+```flux
+mutable greeted
+```
+
+How many times [`greet`](#course-greet) has run.
+
+*Defined on line 2.*
+");
+}
+
+#[test]
+fn html_reference() {
+    let html = project_flux::doc("<prog>", SOURCE, DocFormat::Html).expect("documents");
+    assert!(html.starts_with("<!DOCTYPE html>"), "{}", html);
+    assert!(html.contains("<title>&lt;prog&gt;</title>"), "{}", html);
+    // Every heading has the id the links use.
+    for id in ["purpose-shorten", "course-greet", "constant-limit", "mutable-greeted"] {
+        assert!(html.contains(&format!("<section id=\"{}\">", id)), "no section `{}` in {}", id, html);
+    }
+    assert!(!html.contains("scratch"), "undocumented mutables are left out: {}", html);
+    // A blank `///` line starts a new paragraph, and doc text is escaped.
+    assert!(html.contains("<p>Says hello.</p>\n<p>Uses <a href=\"#purpose-shorten\"><code>shorten</code></a> on long names &amp; counts"), "{}", html);
+    assert!(html.contains("<p class=\"meta\">Calls: <a href=\"#purpose-shorten\"><code>shorten</code></a></p>"), "{}", html);
+    assert!(html.contains("<p class=\"meta\">Called by: <a href=\"#course-greet\"><code>greet</code></a></p>"), "{}", html);
+    assert!(html.ends_with("</body>\n</html>\n"));
+}
+
+#[test]
+fn nothing_to_document() {
+    let markdown = project_flux::doc("empty.fl", "mutable x = 1;\nreport(x);\n", DocFormat::Markdown).expect("documents");
+    assert!(markdown.starts_with("# empty.fl\n\nNothing to document"), "{}", markdown);
+    assert!(project_flux::doc("bad.fl", "course {", DocFormat::Html).is_err());
+}
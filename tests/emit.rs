@@ -0,0 +1,113 @@
+// tests/emit.rs
+//
+// `--emit`: the tokens, AST and bytecode of a program as text.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SOURCE: &str = "\
+mutable total = 0;
+purpose add(a, b) { yield a + b; }
+total = add(total, 2);
+report(total);
+";
+
+fn tmp(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+/// Run `--emit=<stage>` on `path`; return stdout, or stderr if it failed.
+fn emit_file(path: &Path, stage: &str) -> Result<String, String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_project_flux"))
+        .arg(format!("--emit={}", stage))
+        .arg(path)
+        .output()
+        .expect("the binary runs");
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+/// Write `source` to a file named `name` and run `--emit=<stage>` on it.
+fn emit(name: &str, source: &str, stage: &str) -> Result<String, String> {
+    std::fs::write(tmp(name), source).expect("writes the program");
+    emit_file(&tmp(name), stage)
+}
+
+#[test]
+fn tokens() {
+    let tokens = emit("tokens.fl", SOURCE, "tokens").expect("lexes");
+    assert!(tokens.starts_with("     1:1  keyword   `mutable`\n     1:9  ident     `total`\n    1:15  operator  `=`\n"), "{}", tokens);
+    assert!(tokens.ends_with("    4:14  punct     `;`\n     5:1  eof       end of file\n"), "{}", tokens);
+}
+
+#[test]
+fn ast() {
+    assert_eq!(emit("ast.fl", SOURCE, "ast").expect("parses"), "\
+program
+├─ mutable total  (1:1)
+│  └─ int 0  (1:17)
+├─ purpose add(a, b)  (2:1)
+│  └─ yield  (2:21)
+│     └─ binary `+`  (2:27)
+│        ├─ ident a  (2:27)
+│        └─ ident b  (2:31)
+├─ assign total  (3:1)
+│  └─ call add  (3:9)
+│     ├─ ident total  (3:13)
+│     └─ int 2  (3:20)
+└─ expr  (4:1)
+   └─ call report  (4:1)
+      └─ ident total  (4:8)
+");
+}
+
+#[test]
+fn ir() {
+    assert_eq!(emit("ir.fl", SOURCE, "ir").expect("compiles"), "\
+purpose add (arity 2, locals 2)
+
+  0000  jump         main
+add:
+  0001  load_local   0                   ; line 2
+  0002  load_local   1
+  0003  add
+  0004  return
+  0005  push_null
+  0006  return
+main:
+  0007  push_int     0                   ; line 1
+  0008  store_global total
+  0009  load_global  total               ; line 3
+  0010  push_int     2
+  0011  call         add, 2
+  0012  store_global total
+  0013  load_global  total               ; line 4
+  0014  call_native  report, 1
+  0015  pop
+  0016  push_null
+  0017  return
+");
+}
+
+#[test]
+fn ir_of_loaded_bytecode_names_globals() {
+    std::fs::write(tmp("loaded.fl"), SOURCE).expect("writes the program");
+    let status = Command::new(env!("CARGO_BIN_EXE_project_flux"))
+        .arg("compile")
+        .arg(format!("--output={}", tmp("loaded.flc").display()))
+        .arg(tmp("loaded.fl"))
+        .status()
+        .expect("the binary runs");
+    assert!(status.success());
+    let ir = emit_file(&tmp("loaded.flc"), "ir").expect("loads");
+    assert!(ir.contains("store_global total"), "{}", ir);
+}
+
+#[test]
+fn errors_stop_the_dump() {
+    assert!(emit("lex_error.fl", "report(1 $ 2);", "tokens").is_err());
+    assert!(emit("parse_error.fl", "report(;", "ast").is_err());
+    assert!(emit("check_error.fl", "report(missing);", "ir").is_err());
+}
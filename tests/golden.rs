@@ -0,0 +1,14 @@
+// tests/golden.rs
+//
+// Runs the golden files in tests/corpus, the same way `flux test` does.
+use project_flux::golden;
+use std::path::Path;
+
+#[test]
+fn corpus() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let outcomes = golden::run_dir(&dir).expect("tests/corpus is readable");
+    assert!(!outcomes.is_empty(), "no golden files in {}", dir.display());
+    let failures: Vec<String> = outcomes.iter().filter(|o| !o.passed()).map(|o| o.to_string()).collect();
+    assert!(failures.is_empty(), "\n{}", failures.concat());
+}
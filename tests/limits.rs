@@ -0,0 +1,65 @@
+// tests/limits.rs
+//
+// Execution limits set from the command line: each one stops a runaway
+// program with an E0004 error and exit code 3.
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Write `source` to a file named `name` and run it with `flags`; return the
+/// exit code and stderr.
+fn run(name: &str, source: &str, flags: &[&str]) -> (i32, String) {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, source).expect("writes the program");
+    let output = Command::new(env!("CARGO_BIN_EXE_project_flux")).args(flags).arg(&path).output().expect("the binary runs");
+    (output.status.code().expect("exits normally"), String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+const FOREVER: &str = "mutable n = 0;\npersist true {\n    n = n + 1;\n}\n";
+
+#[test]
+fn step_limit() {
+    let (code, stderr) = run("steps.fl", FOREVER, &["--max-steps=1000"]);
+    assert_eq!(code, 3, "{}", stderr);
+    assert!(stderr.contains("error[E0004]") && stderr.contains("step limit of 1000 exceeded"), "{}", stderr);
+}
+
+#[test]
+fn timeout() {
+    let (code, stderr) = run("timeout.fl", FOREVER, &["--timeout=50ms"]);
+    assert_eq!(code, 3, "{}", stderr);
+    assert!(stderr.contains("error[E0004]") && stderr.contains("timed out after 50ms"), "{}", stderr);
+}
+
+#[test]
+fn size_limit() {
+    // Each pass doubles the list, nested.
+    let source = "mutable xs = [1];\npersist true {\n    xs = [xs, xs];\n}\n";
+    let (code, stderr) = run("size.fl", source, &["--max-size=1000"]);
+    assert_eq!(code, 3, "{}", stderr);
+    assert!(stderr.contains("error[E0004]") && stderr.contains("exceeds the size limit of 1000"), "{}", stderr);
+    assert!(stderr.contains(":3:"), "points at the list built: {}", stderr);
+}
+
+#[test]
+fn call_depth_limit() {
+    let source = "purpose down(n) {\n    yield down(n + 1);\n}\ndown(0);\n";
+    let (code, stderr) = run("depth.fl", source, &["--max-depth=50"]);
+    assert_eq!(code, 3, "{}", stderr);
+    assert!(stderr.contains("error[E0004]") && stderr.contains("call depth limit of 50 exceeded"), "{}", stderr);
+}
+
+#[test]
+fn within_limits() {
+    let source = "mutable n = 0;\npersist n < 10 {\n    n = n + 1;\n}\n";
+    let (code, stderr) = run("fine.fl", source, &["--max-steps=10000", "--timeout=5s", "--max-size=10", "--max-depth=10"]);
+    assert_eq!((code, stderr.as_str()), (0, ""));
+}
+
+#[test]
+fn bad_limit_values() {
+    for flag in ["--max-steps=lots", "--timeout=18446744073709551615s", "--timeout=-1"] {
+        let (code, stderr) = run("bad.fl", "mutable n = 0;\n", &[flag]);
+        assert_eq!(code, 1, "{}: {}", flag, stderr);
+        assert!(stderr.contains("invalid value"), "{}: {}", flag, stderr);
+    }
+}
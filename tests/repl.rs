@@ -0,0 +1,72 @@
+// tests/repl.rs
+//
+// `flux repl` driven through the binary: entries continue until they are
+// complete, definitions can be replaced, and errors point at the line of
+// the entry they come from.
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Feed `input` to a REPL session and return its stdout and stderr.
+fn session(input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_project_flux"))
+        .arg("repl")
+        .env("FLUX_HISTORY", std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("flux_history"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the binary runs");
+    child.stdin.take().expect("piped stdin").write_all(input.as_bytes()).expect("writes input");
+    let output = child.wait_with_output().expect("the session ends");
+    (String::from_utf8_lossy(&output.stdout).into_owned(), String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[test]
+fn keeps_state_between_entries() {
+    let (stdout, stderr) = session("mutable x = 20;\npurpose add(a, b) { yield a + b; }\nadd(x, 22);\n");
+    assert_eq!(stderr, "");
+    assert!(stdout.contains("42\n"), "{}", stdout);
+}
+
+#[test]
+fn incomplete_entries_continue() {
+    // Open brackets carry an entry over lines, and so does a last token
+    // that can't end a statement.
+    let (stdout, stderr) = session("purpose add(a, b) {\n    yield a + b;\n}\nadd(1,\n2);\nmutable y = 3 +\n4;\ny;\n");
+    assert_eq!(stderr, "");
+    assert_eq!(stdout.matches("  ... ").count(), 4, "{}", stdout);
+    assert!(stdout.contains("3\n") && stdout.contains("7\n"), "{}", stdout);
+}
+
+#[test]
+fn open_strings_and_comments_continue() {
+    let (stdout, stderr) = session("mutable s = \"\"\"\none\ntwo\n\"\"\";\nreport(s);\n/* a\nblock */ report(3);\n");
+    assert_eq!(stderr, "");
+    assert_eq!(stdout.matches("  ... ").count(), 4, "{}", stdout);
+    assert!(stdout.contains("one\ntwo\n") && stdout.contains("3 \n"), "{}", stdout);
+}
+
+#[test]
+fn blank_lines_and_lex_errors_submit_an_entry() {
+    let (_, stderr) = session("report(1\n\nreport(\"open);\n\n");
+    assert!(stderr.contains("error[E0002]"), "{}", stderr);
+    assert!(stderr.contains("error[E0001]: Unterminated string"), "{}", stderr);
+    // Any other lex error is submitted at once rather than waiting for more.
+    let (stdout, stderr) = session("report(1 $ 2);\n");
+    assert!(stderr.contains("error[E0001]"), "{}", stderr);
+    assert!(!stdout.contains("  ... "), "{}", stdout);
+}
+
+#[test]
+fn definitions_can_be_replaced() {
+    // Code compiled against the old `twice` calls the new one.
+    let (stdout, stderr) = session("purpose twice(x) { yield x * 2; }\ncourse show() { report(twice(5)); }\nshow();\npurpose twice(x) { yield x + x + 1; }\nshow();\ntwice(1);\n");
+    assert_eq!(stderr, "");
+    assert!(stdout.contains("10 \n") && stdout.contains("11 \n") && stdout.contains("3\n"), "{}", stdout);
+}
+
+#[test]
+fn errors_point_at_the_entry_line() {
+    let (_, stderr) = session("mutable x = 1;\nmutable z = 2;\nreport(y);\n");
+    assert!(stderr.contains("<repl>:3:8"), "{}", stderr);
+}